@@ -42,6 +42,7 @@ struct AppDrawer {
 #[derive(Debug, Clone)]
 pub enum Message {
     SearchTextChanged(String),
+    SearchSubmitted,
 }
 
 struct AppWidgets {
@@ -202,6 +203,7 @@ impl SimpleComponent for AppDrawer {
             })
             .forward(sender.input_sender(), |msg| match msg {
                 IconInputOutputMessage::InputChange(text) => Message::SearchTextChanged(text),
+                IconInputOutputMessage::Activate => Message::SearchSubmitted,
             });
 
         container_box.append(search_input.widget());
@@ -246,6 +248,7 @@ impl SimpleComponent for AppDrawer {
             Message::SearchTextChanged(term) => {
                 self.search_text = term;
             }
+            Message::SearchSubmitted => (),
         }
     }
 
@@ -16,6 +16,7 @@ use tracing::{error, info};
 pub struct IconInputCss {
     root_container: Option<Vec<String>>,
     root_container_focused: Option<Vec<String>>,
+    root_container_error: Option<Vec<String>>,
     container: Option<Vec<String>>,
     icon: Option<Vec<String>>,
 }
@@ -27,6 +28,7 @@ impl Default for IconInputCss {
             root_container_focused: Option::from(vec![
                 "icon-input-root-focused-default".to_string()
             ]),
+            root_container_error: Option::from(vec!["icon-input-root-error-default".to_string()]),
             container: Option::from(vec!["icon-input-container-default".to_string()]),
             icon: Option::from(vec!["icon-input-icon-default".to_string()]),
         }
@@ -60,17 +62,22 @@ pub enum InputMessage {
     InputFocusEnter,
     InputFocusLeave,
     Clear,
+    Focus,
+    Activate,
+    SetError(bool),
 }
 
 #[derive(Debug)]
 pub enum OutputMessage {
     InputChange(String),
+    Activate,
 }
 
 pub struct IconInput {
     settings: InitSettings,
     view_password: bool,
     is_focused: bool,
+    has_error: bool,
     input: gtk::Entry,
 }
 
@@ -152,6 +159,10 @@ impl SimpleComponent for IconInput {
             sender.input(InputMessage::InputChange(entry.text().into()));
         }));
 
+        input.connect_activate(clone!(@strong sender => move |_| {
+            sender.input(InputMessage::Activate);
+        }));
+
         let icon_image = gtk::Image::builder().hexpand(false).vexpand(false).build();
 
         match init.placeholder.clone() {
@@ -213,6 +224,7 @@ impl SimpleComponent for IconInput {
             settings: init,
             view_password: false,
             is_focused: false,
+            has_error: false,
             input,
         };
 
@@ -243,6 +255,15 @@ impl SimpleComponent for IconInput {
             InputMessage::Clear => {
                 self.input.set_text("");
             }
+            InputMessage::Focus => {
+                self.input.grab_focus();
+            }
+            InputMessage::Activate => {
+                let _ = sender.output_sender().send(OutputMessage::Activate);
+            }
+            InputMessage::SetError(has_error) => {
+                self.has_error = has_error;
+            }
         }
     }
 
@@ -253,6 +274,12 @@ impl SimpleComponent for IconInput {
                 .set_class_active(&css.join(",").as_str(), self.is_focused),
             None => (),
         }
+        match self.settings.css.root_container_error.to_owned() {
+            Some(css) => widgets
+                .root
+                .set_class_active(&css.join(",").as_str(), self.has_error),
+            None => (),
+        }
         widgets.clear_icon_image.set_visible(self.is_focused);
     }
 
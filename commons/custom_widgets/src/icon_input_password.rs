@@ -14,6 +14,7 @@ use tracing::{error, info};
 pub struct IconInputPasswordCss {
     root_container: Option<Vec<String>>,
     root_container_focused: Option<Vec<String>>,
+    root_container_error: Option<Vec<String>>,
     container: Option<Vec<String>>,
     icon: Option<Vec<String>>,
 }
@@ -25,6 +26,7 @@ impl Default for IconInputPasswordCss {
             root_container_focused: Option::from(vec![
                 "icon-input-root-focused-default".to_string()
             ]),
+            root_container_error: Option::from(vec!["icon-input-root-error-default".to_string()]),
             container: Option::from(vec!["icon-input-container-default".to_string()]),
             icon: Option::from(vec!["icon-input-icon-default".to_string()]),
         }
@@ -45,17 +47,24 @@ pub enum InputMessage {
     InputChange(String),
     InputFocusEnter,
     InputFocusLeave,
+    Clear,
+    Focus,
+    Activate,
+    SetError(bool),
 }
 
 #[derive(Debug)]
 pub enum OutputMessage {
     InputChange(String),
+    Activate,
 }
 
 pub struct IconInputPassword {
     settings: InitSettings,
     is_text_visible: bool,
     is_focused: bool,
+    has_error: bool,
+    input: gtk::Entry,
 }
 
 pub struct ComponentWidgets {
@@ -129,6 +138,10 @@ impl SimpleComponent for IconInputPassword {
             sender.input(InputMessage::InputChange(entry.text().into()));
         }));
 
+        input.connect_activate(clone!(@strong sender => move |_| {
+            sender.input(InputMessage::Activate);
+        }));
+
         let icon_image = gtk::Image::builder().hexpand(false).vexpand(false).build();
 
         match init.placeholder.clone() {
@@ -178,6 +191,8 @@ impl SimpleComponent for IconInputPassword {
             settings: init,
             is_text_visible: false,
             is_focused: false,
+            has_error: false,
+            input: input.clone(),
         };
 
         let widgets = ComponentWidgets {
@@ -207,6 +222,18 @@ impl SimpleComponent for IconInputPassword {
             InputMessage::InputFocusLeave => {
                 self.is_focused = false;
             }
+            InputMessage::Clear => {
+                self.input.set_text("");
+            }
+            InputMessage::Focus => {
+                self.input.grab_focus();
+            }
+            InputMessage::Activate => {
+                let _ = sender.output_sender().send(OutputMessage::Activate);
+            }
+            InputMessage::SetError(has_error) => {
+                self.has_error = has_error;
+            }
         }
     }
 
@@ -241,6 +268,12 @@ impl SimpleComponent for IconInputPassword {
                 .set_class_active(&css.join(",").as_str(), self.is_focused),
             None => (),
         }
+        match self.settings.css.root_container_error.to_owned() {
+            Some(css) => widgets
+                .root
+                .set_class_active(&css.join(",").as_str(), self.has_error),
+            None => (),
+        }
     }
 
     fn shutdown(&mut self, widgets: &mut Self::Widgets, output: relm4::Sender<Self::Output>) {
@@ -5,11 +5,7 @@ pub mod battery {
     tonic::include_proto!("battery");
 }
 
-pub use battery::{
-    power_supply_service_client::PowerSupplyServiceClient,
-    power_supply_service_server::{PowerSupplyService, PowerSupplyServiceServer},
-    Empty,
-};
+pub use battery::{power_supply_service_client::PowerSupplyServiceClient, Empty};
 
 use self::battery::GetPowerSupplyInfoResponse;
 
@@ -26,10 +22,9 @@ impl BatteryManagerClient {
 
     pub async fn get_battery_status(
         &mut self,
-    ) -> Result<(GetPowerSupplyInfoResponse), Box<dyn std::error::Error>> {
+    ) -> Result<GetPowerSupplyInfoResponse, Box<dyn std::error::Error>> {
         let request = tonic::Request::new(Empty {});
         let response = self.client.get_power_supply_info(request).await?;
-        println!("bluetooth response is {:?}", response);
-        Ok((response.into_inner()))
+        Ok(response.into_inner())
     }
 }
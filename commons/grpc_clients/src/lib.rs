@@ -0,0 +1,5 @@
+//! Clients of the network manager and battery gRPC services
+//! shared by the shell components that show the device status
+
+pub mod battery_client;
+pub mod network_client;
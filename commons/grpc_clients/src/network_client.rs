@@ -0,0 +1,50 @@
+use tonic::transport::Channel;
+
+#[allow(non_snake_case)]
+pub mod networkmanager {
+    tonic::include_proto!("networkmanager");
+}
+
+pub use networkmanager::{network_manager_service_client::NetworkManagerServiceClient, Empty};
+
+use self::networkmanager::ScanResult;
+
+pub struct NetworkManagerClient {
+    client: NetworkManagerServiceClient<Channel>,
+}
+
+pub struct ExtendedWifiStatusResponse {
+    pub wifi_on: bool,
+    pub current_network: Option<ScanResult>,
+}
+
+impl NetworkManagerClient {
+    pub async fn new(url: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let client = NetworkManagerServiceClient::connect(url).await?;
+
+        Ok(Self { client })
+    }
+
+    pub async fn get_wireless_network_status(
+        &mut self,
+    ) -> Result<ExtendedWifiStatusResponse, Box<dyn std::error::Error>> {
+        let request = tonic::Request::new(Empty {});
+        let response = self.client.get_wifi_status(request).await?;
+        let wifi_status = response.into_inner();
+        let current_network = match wifi_status.wifi_on {
+            true => {
+                let request = tonic::Request::new(Empty {});
+                match self.client.get_current_network(request).await {
+                    Ok(r) => Option::from(r.into_inner()),
+                    Err(_) => None,
+                }
+            }
+            false => None,
+        };
+
+        Ok(ExtendedWifiStatusResponse {
+            wifi_on: wifi_status.wifi_on,
+            current_network,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::bail;
+use anyhow::Result;
+
+use crate::errors::{IdleManagerError, IdleManagerErrorCodes};
+
+/// # Backlight
+///
+/// Thin wrapper over a sysfs backlight device, used to dim
+/// the display before locking and to restore it on activity
+pub struct Backlight {
+    path: PathBuf,
+}
+
+impl Backlight {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+        }
+    }
+
+    pub fn brightness(&self) -> Result<u32> {
+        self.read_value("brightness")
+    }
+
+    pub fn max_brightness(&self) -> Result<u32> {
+        self.read_value("max_brightness")
+    }
+
+    pub fn set_brightness(&self, value: u32) -> Result<()> {
+        let file_path = self.path.join("brightness");
+        match fs::write(&file_path, value.to_string()) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                bail!(IdleManagerError::new(
+                    IdleManagerErrorCodes::BacklightWriteError,
+                    format!("unable to write brightness to {:?} error: {}", file_path, e),
+                ));
+            }
+        }
+    }
+
+    fn read_value(&self, name: &str) -> Result<u32> {
+        let file_path = self.path.join(name);
+        let value = match fs::read_to_string(&file_path) {
+            Ok(v) => v,
+            Err(e) => {
+                bail!(IdleManagerError::new(
+                    IdleManagerErrorCodes::BacklightReadError,
+                    format!("unable to read {:?} error: {}", file_path, e),
+                ));
+            }
+        };
+
+        match value.trim().parse::<u32>() {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                bail!(IdleManagerError::new(
+                    IdleManagerErrorCodes::BacklightReadError,
+                    format!("invalid value in {:?} error: {}", file_path, e),
+                ));
+            }
+        }
+    }
+}
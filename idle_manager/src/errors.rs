@@ -0,0 +1,67 @@
+use std::fmt;
+
+use tracing::error;
+
+/// # Idle Manager Error Codes
+///
+/// Implements standard errors for the idle manager
+#[derive(Debug, Default, Clone, Copy)]
+pub enum IdleManagerErrorCodes {
+    #[default]
+    UnknownError,
+    SettingsReadError,
+    SettingsParseError,
+    WaylandConnectError,
+    IdleNotifierNotSupported,
+    SeatNotFound,
+    WaylandDispatchError,
+    BacklightReadError,
+    BacklightWriteError,
+    LockScreenSpawnError,
+    LockScreenDbusError,
+    InvalidSettings,
+}
+
+impl fmt::Display for IdleManagerErrorCodes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdleManagerErrorCodes::UnknownError => write!(f, "UnknownError"),
+            IdleManagerErrorCodes::SettingsReadError => write!(f, "SettingsReadError"),
+            IdleManagerErrorCodes::SettingsParseError => write!(f, "SettingsParseError"),
+            IdleManagerErrorCodes::WaylandConnectError => write!(f, "WaylandConnectError"),
+            IdleManagerErrorCodes::IdleNotifierNotSupported => {
+                write!(f, "IdleNotifierNotSupported")
+            }
+            IdleManagerErrorCodes::SeatNotFound => write!(f, "SeatNotFound"),
+            IdleManagerErrorCodes::WaylandDispatchError => write!(f, "WaylandDispatchError"),
+            IdleManagerErrorCodes::BacklightReadError => write!(f, "BacklightReadError"),
+            IdleManagerErrorCodes::BacklightWriteError => write!(f, "BacklightWriteError"),
+            IdleManagerErrorCodes::LockScreenSpawnError => write!(f, "LockScreenSpawnError"),
+            IdleManagerErrorCodes::LockScreenDbusError => write!(f, "LockScreenDbusError"),
+            IdleManagerErrorCodes::InvalidSettings => write!(f, "InvalidSettings"),
+        }
+    }
+}
+
+/// # IdleManagerError
+///
+/// Implements a standard error type for all idle manager related errors
+/// includes the error code (`IdleManagerErrorCodes`) and a message
+#[derive(Debug, Default)]
+pub struct IdleManagerError {
+    pub code: IdleManagerErrorCodes,
+    pub message: String,
+}
+
+impl IdleManagerError {
+    pub fn new(code: IdleManagerErrorCodes, message: String) -> Self {
+        error!("error: (code: {:?}, message: {})", code, message);
+        Self { code, message }
+    }
+}
+
+impl std::fmt::Display for IdleManagerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(code: {:?}, message: {})", self.code, self.message)
+    }
+}
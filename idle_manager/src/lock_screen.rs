@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use dbus::blocking::Connection;
+use tracing::info;
+
+use crate::errors::{IdleManagerError, IdleManagerErrorCodes};
+
+const BUS_NAME: &str = "org.mecha.LockScreen";
+const OBJECT_PATH: &str = "/org/mecha/LockScreen";
+const INTERFACE_NAME: &str = "org.mecha.LockScreen";
+
+/// Time the lock screen gets to answer a call
+const CALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Errors meaning no lock screen serves the interface right now
+const NOT_RUNNING_ERRORS: &[&str] = &[
+    "org.freedesktop.DBus.Error.ServiceUnknown",
+    "org.freedesktop.DBus.Error.NameHasNoOwner",
+];
+
+/// # Lock
+///
+/// Locks the lock screen serving `org.mecha.LockScreen`, which stays
+/// around hidden between locks. False when none is running
+pub fn lock(system_bus: bool) -> Result<bool> {
+    let connection_result = match system_bus {
+        true => Connection::new_system(),
+        false => Connection::new_session(),
+    };
+    let conn = match connection_result {
+        Ok(conn) => conn,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::LockScreenDbusError,
+                format!("unable to connect to dbus error: {}", e),
+            ));
+        }
+    };
+    let proxy = conn.with_proxy(BUS_NAME, OBJECT_PATH, CALL_TIMEOUT);
+
+    let result = proxy
+        .method_call(INTERFACE_NAME, "IsLocked", ())
+        .and_then(|(is_locked,): (bool,)| match is_locked {
+            true => Ok(true),
+            false => proxy.method_call(INTERFACE_NAME, "Lock", ()).map(|_: ()| false),
+        });
+    match result {
+        Ok(true) => {
+            info!(task = "lock", "lock screen is already locked");
+            Ok(true)
+        }
+        Ok(false) => {
+            info!(task = "lock", "locked the running lock screen");
+            Ok(true)
+        }
+        Err(e) if e.name().is_some_and(|name| NOT_RUNNING_ERRORS.contains(&name)) => Ok(false),
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::LockScreenDbusError,
+                format!("unable to lock the lock screen over dbus error: {}", e),
+            ));
+        }
+    }
+}
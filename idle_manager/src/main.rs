@@ -0,0 +1,286 @@
+use std::process::{Child, Command};
+
+use anyhow::bail;
+use anyhow::Result;
+use tracing::{error, info};
+use wayland_client::{
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry, wl_seat},
+    Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+
+mod backlight;
+mod lock_screen;
+mod settings;
+pub mod errors;
+
+use crate::backlight::Backlight;
+use crate::errors::{IdleManagerError, IdleManagerErrorCodes};
+use crate::settings::{timeout_millis, IdleManagerSettings};
+
+/// # Idle Stage
+///
+/// Each idle notification registered with the compositor is tagged
+/// with the stage it drives, so the events can be told apart
+#[derive(Debug, Clone, Copy)]
+enum IdleStage {
+    Dim,
+    Lock,
+}
+
+/// # Idle Manager state
+///
+/// Notifications are requested with `get_idle_notification`, which the
+/// compositor holds back while an idle inhibitor (e.g. video playback)
+/// is active, so inhibitors are honoured without extra bookkeeping
+struct IdleManager {
+    settings: IdleManagerSettings,
+    backlight: Backlight,
+    saved_brightness: Option<u32>,
+    lock_screen: Option<Child>,
+}
+
+impl IdleManager {
+    fn new(settings: IdleManagerSettings) -> Self {
+        let backlight = Backlight::new(&settings.idle.backlight_path);
+        Self {
+            settings,
+            backlight,
+            saved_brightness: None,
+            lock_screen: None,
+        }
+    }
+
+    fn dim(&mut self) {
+        if self.saved_brightness.is_some() {
+            return;
+        }
+
+        let brightness = match self.backlight.brightness() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        let max_brightness = match self.backlight.max_brightness() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        let dimmed = max_brightness * self.settings.idle.dim_brightness.min(100) / 100;
+        if dimmed >= brightness {
+            return;
+        }
+
+        info!(task = "dim", "dimming display from {} to {}", brightness, dimmed);
+        if self.backlight.set_brightness(dimmed).is_ok() {
+            self.saved_brightness = Some(brightness);
+        }
+    }
+
+    fn undim(&mut self) {
+        if let Some(brightness) = self.saved_brightness.take() {
+            info!(task = "undim", "restoring display brightness to {}", brightness);
+            let _ = self.backlight.set_brightness(brightness);
+        }
+    }
+
+    fn lock(&mut self) {
+        // a running lock screen is only told to lock, it stays around between locks
+        if let Ok(true) = lock_screen::lock(self.settings.lock_screen.system_bus) {
+            return;
+        }
+
+        // started but not serving dbus (yet)
+        if self.is_locked() {
+            info!(task = "lock", "lock screen is already running");
+            return;
+        }
+
+        let lock_screen_settings = &self.settings.lock_screen;
+        info!(task = "lock", "starting lock screen {}", lock_screen_settings.bin_path);
+        match Command::new(&lock_screen_settings.bin_path)
+            .arg("--settings")
+            .arg(&lock_screen_settings.settings_path)
+            .spawn()
+        {
+            Ok(child) => self.lock_screen = Some(child),
+            Err(e) => {
+                IdleManagerError::new(
+                    IdleManagerErrorCodes::LockScreenSpawnError,
+                    format!("unable to start lock screen error: {}", e),
+                );
+            }
+        }
+    }
+
+    fn is_locked(&mut self) -> bool {
+        match self.lock_screen.as_mut() {
+            Some(child) => match child.try_wait() {
+                Ok(None) => true,
+                Ok(Some(_)) => {
+                    self.lock_screen = None;
+                    false
+                }
+                Err(e) => {
+                    error!("error checking lock screen status: {}", e);
+                    false
+                }
+            },
+            None => false,
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for IdleManager {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for IdleManager {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for IdleManager {
+    fn event(
+        _: &mut Self,
+        _: &ExtIdleNotifierV1,
+        _: <ExtIdleNotifierV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, IdleStage> for IdleManager {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        stage: &IdleStage,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        info!("idle event {:?} for stage {:?}", event, stage);
+        match (stage, event) {
+            (IdleStage::Dim, ext_idle_notification_v1::Event::Idled) => state.dim(),
+            (IdleStage::Dim, ext_idle_notification_v1::Event::Resumed) => state.undim(),
+            (IdleStage::Lock, ext_idle_notification_v1::Event::Idled) => state.lock(),
+            _ => (),
+        }
+    }
+}
+
+fn run(settings: IdleManagerSettings) -> Result<()> {
+    let connection = match Connection::connect_to_env() {
+        Ok(c) => c,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::WaylandConnectError,
+                format!("unable to connect to wayland display error: {}", e),
+            ));
+        }
+    };
+
+    let (globals, mut event_queue) = match registry_queue_init::<IdleManager>(&connection) {
+        Ok(r) => r,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::WaylandConnectError,
+                format!("unable to read wayland globals error: {}", e),
+            ));
+        }
+    };
+    let qh = event_queue.handle();
+
+    let seat: wl_seat::WlSeat = match globals.bind(&qh, 1..=1, ()) {
+        Ok(s) => s,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::SeatNotFound,
+                format!("unable to bind wl_seat error: {}", e),
+            ));
+        }
+    };
+
+    let idle_notifier: ExtIdleNotifierV1 = match globals.bind(&qh, 1..=1, ()) {
+        Ok(n) => n,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::IdleNotifierNotSupported,
+                format!("compositor does not support ext-idle-notify-v1 error: {}", e),
+            ));
+        }
+    };
+
+    // the timeouts were validated to fit in milliseconds
+    let idle_settings = settings.idle.clone();
+    let _dim_notification = match idle_settings.dim_timeout {
+        0 => None,
+        timeout => Some(idle_notifier.get_idle_notification(
+            timeout_millis(timeout).unwrap_or(u32::MAX),
+            &seat,
+            &qh,
+            IdleStage::Dim,
+        )),
+    };
+    let _lock_notification = idle_notifier.get_idle_notification(
+        timeout_millis(idle_settings.lock_timeout).unwrap_or(u32::MAX),
+        &seat,
+        &qh,
+        IdleStage::Lock,
+    );
+
+    let mut idle_manager = IdleManager::new(settings);
+    loop {
+        if let Err(e) = event_queue.blocking_dispatch(&mut idle_manager) {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::WaylandDispatchError,
+                format!("wayland dispatch failed error: {}", e),
+            ));
+        }
+    }
+}
+
+fn main() {
+    // Enables logger
+    // install global collector configured based on RUST_LOG env var.
+    tracing_subscriber::fmt()
+        .pretty()
+        .with_env_filter("mecha_idle_manager=trace")
+        .with_thread_names(true)
+        .init();
+
+    let settings = settings::read_settings_yml().unwrap_or_default();
+
+    info!(
+        task = "initalize_settings",
+        "settings initialized for Idle Manager: {:?}", settings
+    );
+
+    if settings::validate_settings(&settings).is_err() {
+        return;
+    }
+
+    if let Err(e) = run(settings) {
+        error!("idle manager exited: {}", e);
+    }
+}
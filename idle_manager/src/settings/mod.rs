@@ -0,0 +1,228 @@
+use crate::errors::{IdleManagerError, IdleManagerErrorCodes};
+use anyhow::bail;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, fs::File, os::unix::fs::PermissionsExt, path::PathBuf};
+use tracing::{debug, info};
+
+/// # Idle Manager Settings
+///
+/// Struct representing the settings.yml configuration file,
+/// this file lets you control when the display is dimmed and
+/// when the lock screen is shown
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct IdleManagerSettings {
+    pub idle: IdleSettings,
+    pub lock_screen: LockScreenSettings,
+}
+
+/// # Idle Settings
+///
+/// Part of the settings.yml to control the idle timeouts,
+/// all timeouts are in seconds
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct IdleSettings {
+    pub dim_timeout: u32,       // Inactivity before dimming, 0 disables dimming
+    pub lock_timeout: u32,      // Inactivity before locking
+    pub dim_brightness: u32,    // Brightness in percent of max while dimmed
+    pub backlight_path: String, // Sysfs backlight device
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            dim_timeout: 30,
+            lock_timeout: 60,
+            dim_brightness: 20,
+            backlight_path: "/sys/class/backlight/backlight".to_string(),
+        }
+    }
+}
+
+/// # Lock Screen Settings
+///
+/// Part of the settings.yml for the lock screen locked over dbus once the
+/// lock timeout is reached, the binary is started when none is running
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct LockScreenSettings {
+    pub bin_path: String,
+    pub settings_path: String,
+    #[serde(default)]
+    pub system_bus: bool, // Bus the lock screen serves org.mecha.LockScreen on, the session bus by default
+}
+
+/// # Reads Settings path from arg
+///
+/// Reads the `-s` or `--settings` argument for the path
+pub fn read_settings_path_from_args() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 2 && (args[1] == "-s" || args[1] == "--settings") {
+        debug!("Using settings path from argument - {}", args[2]);
+        return Some(args[2].clone());
+    }
+    None
+}
+
+/// # Reads Settings YML
+///
+/// Reads the `settings.yml` and parsers to IdleManagerSettings
+///
+/// **Important**: Ensure all fields are present in the yml due to strict parsing
+pub fn read_settings_yml() -> Result<IdleManagerSettings> {
+    let mut file_path = PathBuf::from(
+        std::env::var("MECHA_IDLE_MANAGER_SETTINGS_PATH")
+            .unwrap_or(String::from("settings.yml")),
+    ); // Get path of the library
+
+    // read from args
+    if let Some(file_path_in_args) = read_settings_path_from_args() {
+        file_path = PathBuf::from(file_path_in_args);
+    }
+
+    info!(
+        task = "read_settings",
+        "settings file location - {:?}", file_path
+    );
+
+    // open file
+    let settings_file_handle = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::SettingsReadError,
+                format!("cannot read the settings.yml in the path - {}", e),
+            ));
+        }
+    };
+
+    // read and parse
+    let config: IdleManagerSettings = match serde_yaml::from_reader(settings_file_handle) {
+        Ok(config) => config,
+        Err(e) => {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::SettingsParseError,
+                format!("error parsing the settings.yml - {}", e),
+            ));
+        }
+    };
+
+    Ok(config)
+}
+
+/// # Validate Settings
+///
+/// Checks up front that the lock screen can be started, an idle
+/// manager that finds out only once the device should lock is of no use
+pub fn validate_settings(settings: &IdleManagerSettings) -> Result<()> {
+    let idle = &settings.idle;
+    if idle.lock_timeout == 0 {
+        bail!(IdleManagerError::new(
+            IdleManagerErrorCodes::InvalidSettings,
+            "idle.lock_timeout must be at least 1 second".to_string(),
+        ));
+    }
+    for (name, timeout) in [
+        ("dim_timeout", idle.dim_timeout),
+        ("lock_timeout", idle.lock_timeout),
+    ] {
+        if timeout_millis(timeout).is_none() {
+            bail!(IdleManagerError::new(
+                IdleManagerErrorCodes::InvalidSettings,
+                format!(
+                    "idle.{} of {} seconds is above the maximum of {} seconds",
+                    name,
+                    timeout,
+                    u32::MAX / 1000
+                ),
+            ));
+        }
+    }
+
+    let bin_path = &settings.lock_screen.bin_path;
+    if bin_path.is_empty() {
+        bail!(IdleManagerError::new(
+            IdleManagerErrorCodes::InvalidSettings,
+            "lock_screen.bin_path is not set".to_string(),
+        ));
+    }
+    let is_executable = fs::metadata(bin_path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false);
+    if !is_executable {
+        bail!(IdleManagerError::new(
+            IdleManagerErrorCodes::InvalidSettings,
+            format!("lock screen {} is not an executable file", bin_path),
+        ));
+    }
+    Ok(())
+}
+
+/// Milliseconds the compositor is asked to wait for, None
+/// when the seconds do not fit
+pub fn timeout_millis(seconds: u32) -> Option<u32> {
+    seconds.checked_mul(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(dim_timeout: u32, lock_timeout: u32) -> IdleManagerSettings {
+        IdleManagerSettings {
+            idle: IdleSettings {
+                dim_timeout,
+                lock_timeout,
+                ..Default::default()
+            },
+            lock_screen: LockScreenSettings {
+                bin_path: "/bin/sh".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn settings_parse() {
+        let yml = "
+idle:
+  dim_timeout: 0
+  lock_timeout: 300
+  dim_brightness: 20
+  backlight_path: /sys/class/backlight/panel
+lock_screen:
+  bin_path: /usr/bin/mecha_lock_screen
+  settings_path: /etc/mecha/lock_screen.yml
+";
+
+        let settings: IdleManagerSettings = serde_yaml::from_str(yml).unwrap();
+
+        assert_eq!(settings.idle.dim_timeout, 0);
+        assert_eq!(settings.idle.lock_timeout, 300);
+        assert_eq!(settings.idle.backlight_path, "/sys/class/backlight/panel");
+        assert!(!settings.lock_screen.system_bus);
+    }
+
+    #[test]
+    fn timeouts_are_converted_to_millis() {
+        assert_eq!(timeout_millis(60), Some(60_000));
+        assert_eq!(timeout_millis(u32::MAX / 1000), Some(u32::MAX / 1000 * 1000));
+        assert_eq!(timeout_millis(u32::MAX / 1000 + 1), None);
+    }
+
+    #[test]
+    fn timeouts_in_range_are_accepted() {
+        assert!(validate_settings(&settings(0, 1)).is_ok());
+        assert!(validate_settings(&settings(30, u32::MAX / 1000)).is_ok());
+    }
+
+    #[test]
+    fn zero_lock_timeout_is_rejected() {
+        assert!(validate_settings(&settings(30, 0)).is_err());
+    }
+
+    #[test]
+    fn overflowing_timeouts_are_rejected() {
+        assert!(validate_settings(&settings(30, u32::MAX / 1000 + 1)).is_err());
+        assert!(validate_settings(&settings(u32::MAX, 60)).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use mecha_launcher::protocol::{default_socket_path, ComponentStatus, LogEntry, Request, Response};
+
+const USAGE: &str = "usage: mecha-launcherctl [--socket PATH] <command>
+
+commands:
+  status                     list the components with their state
+  start <component>          start a component
+  stop <component>           stop a component
+  restart <component>        stop, then start a component
+  logs <component> [lines] [--follow]
+                             show when a component started, stopped and exited,
+                             then the last lines of its output, --follow keeps
+                             printing the output as it comes
+  ping <component>           feed the watchdog of a component
+
+the output of a component is read from <component>.log in the log directory,
+$XDG_STATE_HOME/mecha-launcher unless the settings set another, it also goes
+to the journal when enabled";
+
+/// Lines shown by `logs` unless asked otherwise
+const DEFAULT_LOG_LINES: usize = 50;
+
+/// How often `logs --follow` looks for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let mut socket_path = default_socket_path();
+    if args.len() > 1 && args[0] == "--socket" {
+        socket_path = PathBuf::from(args[1].clone());
+        args.drain(..2);
+    }
+    let is_follow = args.iter().any(|arg| arg == "--follow");
+    args.retain(|arg| arg != "--follow");
+
+    // only the output of `logs` can be followed
+    let request = match parse_request(&args) {
+        Some(request) if !is_follow || matches!(request, Request::Logs { .. }) => request,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match send(&socket_path, &request) {
+        Ok(Response::Error { message }) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+        Ok(Response::Logs { entries, output }) => {
+            print_logs(&entries);
+            let (Some(output), Request::Logs { lines, .. }) = (output, request) else {
+                return ExitCode::SUCCESS;
+            };
+            match print_output(&output, lines, is_follow) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("unable to read the output in {:?}: {}", output, e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Ok(response) => {
+            print_response(response);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("unable to reach the launcher at {:?}: {}", socket_path, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_request(args: &[String]) -> Option<Request> {
+    let component = || args.get(1).cloned();
+    match args.first()?.as_str() {
+        "status" => Some(Request::Status),
+        "start" => Some(Request::Start {
+            component: component()?,
+        }),
+        "stop" => Some(Request::Stop {
+            component: component()?,
+        }),
+        "restart" => Some(Request::Restart {
+            component: component()?,
+        }),
+        "ping" => Some(Request::Ping {
+            component: component()?,
+        }),
+        "logs" => Some(Request::Logs {
+            component: component()?,
+            lines: match args.get(2) {
+                Some(lines) => lines.parse().ok()?,
+                None => DEFAULT_LOG_LINES,
+            },
+        }),
+        _ => None,
+    }
+}
+
+fn send(socket_path: &PathBuf, request: &Request) -> std::io::Result<Response> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+fn print_response(response: Response) {
+    match response {
+        Response::Ok => (),
+        Response::Status { components } => print_status(&components),
+        Response::Logs { entries, .. } => print_logs(&entries),
+        Response::Error { message } => eprintln!("error: {}", message),
+    }
+}
+
+fn print_status(components: &[ComponentStatus]) {
+    println!(
+        "{:<20} {:<12} {:>8} {:>10} {:>9}",
+        "COMPONENT", "STATE", "PID", "UPTIME", "RESTARTS"
+    );
+    for component in components {
+        let pid = component
+            .pid
+            .map(|pid| pid.to_string())
+            .unwrap_or("-".to_string());
+        let uptime = component
+            .uptime
+            .map(format_duration)
+            .unwrap_or("-".to_string());
+        println!(
+            "{:<20} {:<12} {:>8} {:>10} {:>9}",
+            component.name, component.state, pid, uptime, component.restarts
+        );
+    }
+
+    let failures: Vec<&ComponentStatus> = components
+        .iter()
+        .filter(|component| component.error.is_some())
+        .collect();
+    if !failures.is_empty() {
+        println!();
+    }
+    for component in failures {
+        println!(
+            "{}: {}",
+            component.name,
+            component.error.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+fn print_logs(entries: &[LogEntry]) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    for entry in entries {
+        let age = format_duration(now.saturating_sub(entry.timestamp));
+        println!("{:>10} ago  {}", age, entry.message);
+    }
+}
+
+/// Prints the last `lines` of the output, then with `is_follow`
+/// what the component goes on to print until interrupted
+fn print_output(path: &Path, lines: usize, is_follow: bool) -> io::Result<()> {
+    let (output, position) = tail(path, lines)?;
+    if !output.is_empty() {
+        println!();
+    }
+    for line in output {
+        println!("{}", line);
+    }
+    if is_follow {
+        follow(path, position)?;
+    }
+    Ok(())
+}
+
+/// Last `count` lines of the log, taking the rotated log in when the
+/// current one is short of them, with the length of the current log
+fn tail(path: &Path, count: usize) -> io::Result<(VecDeque<String>, u64)> {
+    let mut rotated_path = path.as_os_str().to_owned();
+    rotated_path.push(".1");
+
+    let mut lines = VecDeque::new();
+    read_lines(Path::new(&rotated_path), count, &mut lines)?;
+    let length = read_lines(path, count, &mut lines)?;
+    Ok((lines, length))
+}
+
+/// Keeps the last `count` lines of the file in `lines`,
+/// giving the bytes read, none for a missing file
+fn read_lines(path: &Path, count: usize, lines: &mut VecDeque<String>) -> io::Result<u64> {
+    let Some(file) = open_at(path, 0)? else {
+        return Ok(0);
+    };
+    let mut reader = BufReader::new(file);
+    let mut length = 0;
+    let mut line = vec![];
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line)? {
+            0 => return Ok(length),
+            read => length += read as u64,
+        }
+        if count == 0 {
+            continue;
+        }
+        if lines.len() == count {
+            lines.pop_front();
+        }
+        let text = String::from_utf8_lossy(&line);
+        lines.push_back(text.trim_end_matches(['\n', '\r']).to_string());
+    }
+}
+
+/// Prints what is appended to the log from `position` on, once the log
+/// is rotated the rest of the old one is printed before the new one. A log
+/// rotated more than once between two looks leaves out the ones in between
+fn follow(path: &Path, position: u64) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut log = open_at(path, position)?;
+    loop {
+        let inode = |file: &File| file.metadata().map(|metadata| metadata.ino()).ok();
+        let current_inode = File::open(path).ok().as_ref().and_then(inode);
+        let is_rotated = current_inode.is_some() && current_inode != log.as_ref().and_then(inode);
+
+        if let Some(file) = log.as_mut() {
+            io::copy(file, &mut stdout)?;
+            stdout.flush()?;
+        }
+        if is_rotated {
+            log = open_at(path, 0)?;
+            continue;
+        }
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// Opens the file read from `position` on, a component
+/// that has not printed anything yet has none
+fn open_at(path: &Path, position: u64) -> io::Result<Option<File>> {
+    match File::open(path) {
+        Ok(mut file) => {
+            file.seek(SeekFrom::Start(position))?;
+            Ok(Some(file))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Seconds as `1h2m3s`, leaving out the leading zero units
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m{}s", minutes, seconds),
+        _ => format!("{}h{}m{}s", hours, minutes, seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, process};
+
+    #[test]
+    fn tail_takes_the_rotated_log_in() {
+        let directory = env::temp_dir().join(format!("launcherctl-tail-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("app.log");
+        fs::write(directory.join("app.log.1"), "first\nsecond\n").unwrap();
+        fs::write(&path, "third\nfourth\n").unwrap();
+
+        let (three, length) = tail(&path, 3).unwrap();
+        let (all, _) = tail(&path, 10).unwrap();
+        let (missing, missing_length) = tail(&directory.join("other.log"), 3).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(three, ["second", "third", "fourth"]);
+        assert_eq!(length, 13);
+        assert_eq!(all, ["first", "second", "third", "fourth"]);
+        assert!(missing.is_empty());
+        assert_eq!(missing_length, 0);
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::{self, Permissions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use mecha_launcher::protocol::{Request, Response};
+use tracing::{error, info};
+
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::launcher::Event;
+
+/// Longest a client waits on the supervision loop to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// # Serve
+///
+/// Listens on the control socket, each line a client sends is a JSON
+/// `Request` that is handed to the supervision loop, its `Response` is
+/// written back as a line of JSON
+pub fn serve(socket_path: &Path, events: Sender<Event>) -> Result<()> {
+    // a socket left behind by an earlier run would fail the bind
+    let _ = fs::remove_file(socket_path);
+    let listener = match UnixListener::bind(socket_path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::ControlSocketBindError,
+                format!("unable to bind control socket {:?} error: {}", socket_path, e),
+            ));
+        }
+    };
+    let _ = fs::set_permissions(socket_path, Permissions::from_mode(0o600));
+    info!(task = "control", "listening on {:?}", socket_path);
+
+    let spawn_result = thread::Builder::new()
+        .name("control".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let events = events.clone();
+                        thread::spawn(move || handle_client(stream, events));
+                    }
+                    Err(e) => error!(task = "control", "unable to accept client: {}", e),
+                }
+            }
+        });
+    if let Err(e) = spawn_result {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::ControlSocketBindError,
+            format!("unable to start control thread error: {}", e),
+        ));
+    }
+    Ok(())
+}
+
+fn handle_client(stream: UnixStream, events: Sender<Event>) {
+    let reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(e) => {
+            error!(task = "control", "unable to read from client: {}", e);
+            return;
+        }
+    };
+    let mut writer = stream;
+
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => dispatch(request, &events),
+            Err(e) => Response::Error {
+                message: format!("invalid request: {}", e),
+            },
+        };
+        let Ok(mut response) = serde_json::to_string(&response) else {
+            return;
+        };
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn dispatch(request: Request, events: &Sender<Event>) -> Response {
+    let (reply, replies) = mpsc::channel();
+    if events.send(Event::Control { request, reply }).is_err() {
+        return Response::Error {
+            message: "launcher is shutting down".to_string(),
+        };
+    }
+    replies
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or(Response::Error {
+            message: "launcher did not answer".to_string(),
+        })
+}
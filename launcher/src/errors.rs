@@ -13,6 +13,24 @@ pub enum LauncherErrorCodes {
     SettingsParseError,
     ThemeReadError,
     ThemeParseError,
+    EventChannelClosed,
+    ChildWaitError,
+    ComponentCrashLoop,
+    ControlSocketBindError,
+    SignalWatchError,
+    InvalidDependency,
+    DependencyCycle,
+    NotifySocketBindError,
+    ComponentNotReady,
+    InvalidComponentName,
+    LogOpenError,
+    SettingsWatchError,
+    ComponentSpawnError,
+    ComponentNotExecutable,
+    CgroupSetupError,
+    InvalidLimits,
+    WatchdogTimeout,
+    DependencyFailed,
 }
 
 impl fmt::Display for LauncherErrorCodes {
@@ -23,6 +41,24 @@ impl fmt::Display for LauncherErrorCodes {
             LauncherErrorCodes::SettingsParseError => write!(f, "SettingsParseError"),
             LauncherErrorCodes::ThemeReadError => write!(f, "ThemeReadError"),
             LauncherErrorCodes::ThemeParseError => write!(f, "ThemeParseError"),
+            LauncherErrorCodes::EventChannelClosed => write!(f, "EventChannelClosed"),
+            LauncherErrorCodes::ChildWaitError => write!(f, "ChildWaitError"),
+            LauncherErrorCodes::ComponentCrashLoop => write!(f, "ComponentCrashLoop"),
+            LauncherErrorCodes::ControlSocketBindError => write!(f, "ControlSocketBindError"),
+            LauncherErrorCodes::SignalWatchError => write!(f, "SignalWatchError"),
+            LauncherErrorCodes::InvalidDependency => write!(f, "InvalidDependency"),
+            LauncherErrorCodes::DependencyCycle => write!(f, "DependencyCycle"),
+            LauncherErrorCodes::NotifySocketBindError => write!(f, "NotifySocketBindError"),
+            LauncherErrorCodes::ComponentNotReady => write!(f, "ComponentNotReady"),
+            LauncherErrorCodes::InvalidComponentName => write!(f, "InvalidComponentName"),
+            LauncherErrorCodes::LogOpenError => write!(f, "LogOpenError"),
+            LauncherErrorCodes::SettingsWatchError => write!(f, "SettingsWatchError"),
+            LauncherErrorCodes::ComponentSpawnError => write!(f, "ComponentSpawnError"),
+            LauncherErrorCodes::ComponentNotExecutable => write!(f, "ComponentNotExecutable"),
+            LauncherErrorCodes::CgroupSetupError => write!(f, "CgroupSetupError"),
+            LauncherErrorCodes::InvalidLimits => write!(f, "InvalidLimits"),
+            LauncherErrorCodes::WatchdogTimeout => write!(f, "WatchdogTimeout"),
+            LauncherErrorCodes::DependencyFailed => write!(f, "DependencyFailed"),
         }
     }
 }
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use anyhow::Result;
+
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::Component;
+
+/// # Start Order
+///
+/// Orders the components so every one comes after its `after` and
+/// `requires` dependencies, otherwise keeping the order of the names.
+/// An enabled component may not require a disabled one, it would never start
+pub fn start_order(components: &BTreeMap<String, Component>) -> Result<Vec<String>> {
+    let mut dependencies: Vec<(&String, Vec<&String>)> = vec![];
+    for (name, component) in components {
+        let mut component_dependencies = vec![];
+        for dependency in component.after.iter().chain(&component.requires) {
+            if !components.contains_key(dependency) {
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::InvalidDependency,
+                    format!("{} depends on unknown component {}", name, dependency),
+                ));
+            }
+            if component.enabled
+                && component.requires.contains(dependency)
+                && !components[dependency].enabled
+            {
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::InvalidDependency,
+                    format!("{} requires disabled component {}", name, dependency),
+                ));
+            }
+            component_dependencies.push(dependency);
+        }
+        dependencies.push((name, component_dependencies));
+    }
+
+    let mut order: Vec<&String> = vec![];
+    while order.len() < dependencies.len() {
+        let next = dependencies.iter().find(|(name, component_dependencies)| {
+            !order.contains(name)
+                && component_dependencies
+                    .iter()
+                    .all(|dependency| order.contains(dependency))
+        });
+        match next {
+            Some((name, _)) => order.push(name),
+            None => {
+                let blocked: Vec<&str> = dependencies
+                    .iter()
+                    .filter(|(name, _)| !order.contains(name))
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::DependencyCycle,
+                    format!("dependency cycle among: {}", blocked.join(", ")),
+                ));
+            }
+        }
+    }
+    Ok(order.into_iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(after: &[&str], requires: &[&str]) -> Component {
+        Component {
+            after: after.iter().map(|name| name.to_string()).collect(),
+            requires: requires.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dependencies_start_first() {
+        let components = BTreeMap::from([
+            ("app".to_string(), component(&["shell"], &[])),
+            ("bar".to_string(), component(&[], &[])),
+            ("shell".to_string(), component(&[], &["wm"])),
+            ("wm".to_string(), component(&[], &[])),
+        ]);
+
+        let order = start_order(&components).unwrap();
+
+        assert_eq!(order, ["bar", "wm", "shell", "app"]);
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let components = BTreeMap::from([
+            ("a".to_string(), component(&["b"], &[])),
+            ("b".to_string(), component(&[], &["a"])),
+            ("c".to_string(), component(&[], &[])),
+        ]);
+
+        let error = start_order(&components).unwrap_err();
+
+        assert!(error.to_string().contains("dependency cycle among: a, b"));
+    }
+
+    #[test]
+    fn unknown_dependencies_are_rejected() {
+        let components = BTreeMap::from([("a".to_string(), component(&[], &["missing"]))]);
+
+        assert!(start_order(&components).is_err());
+    }
+
+    #[test]
+    fn requiring_disabled_components_is_rejected() {
+        let enabled = |after: &[&str], requires: &[&str]| Component {
+            enabled: true,
+            ..component(after, requires)
+        };
+        let requires_disabled = BTreeMap::from([
+            ("app".to_string(), enabled(&[], &["shell"])),
+            ("shell".to_string(), component(&[], &[])),
+        ]);
+        let after_disabled = BTreeMap::from([
+            ("app".to_string(), enabled(&["shell"], &[])),
+            ("shell".to_string(), component(&[], &[])),
+        ]);
+        let both_disabled = BTreeMap::from([
+            ("app".to_string(), component(&[], &["shell"])),
+            ("shell".to_string(), component(&[], &[])),
+        ]);
+
+        let error = start_order(&requires_disabled).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("app requires disabled component shell"));
+        assert!(start_order(&after_disabled).is_ok());
+        assert!(start_order(&both_disabled).is_ok());
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    mem::offset_of,
+    os::unix::{io::AsRawFd, process::CommandExt},
+    path::PathBuf,
+    process::{self, Command},
+};
+
+use anyhow::bail;
+use anyhow::Result;
+use tracing::info;
+
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::{CgroupSettings, Limits};
+
+/// Where the cgroup v2 hierarchy is mounted
+const CGROUP_MOUNT: &str = "/sys/fs/cgroup";
+
+/// Controllers the component cgroups get
+const CONTROLLERS: &str = "+memory +cpu";
+
+/// Cgroup the launcher moves itself to, component names can not have a dot
+const LAUNCHER_CGROUP: &str = "launcher.scope";
+
+/// System calls a shell component has no use for
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_writev,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+    libc::SYS_reboot,
+    libc::SYS_kexec_load,
+    libc::SYS_kexec_file_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_bpf,
+    libc::SYS_perf_event_open,
+    libc::SYS_acct,
+    libc::SYS_quotactl,
+    libc::SYS_settimeofday,
+    libc::SYS_clock_settime,
+    libc::SYS_adjtimex,
+    libc::SYS_sethostname,
+    libc::SYS_setdomainname,
+    libc::SYS_keyctl,
+    libc::SYS_add_key,
+    libc::SYS_request_key,
+    libc::SYS_open_by_handle_at,
+    libc::SYS_userfaultfd,
+];
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// # Cgroup Tree
+///
+/// The cgroup v2 subtree delegated to the launcher. Once controllers are
+/// enabled processes may only live in leaf cgroups, so the launcher moves
+/// itself to `<root>/launcher.scope` and every component gets `<root>/<name>`
+pub struct CgroupTree {
+    root: PathBuf,
+}
+
+impl CgroupTree {
+    pub fn open(settings: &CgroupSettings) -> Result<CgroupTree> {
+        let root = match settings.root.is_empty() {
+            true => own_cgroup()?,
+            false => PathBuf::from(&settings.root),
+        };
+
+        let launcher_cgroup = root.join(LAUNCHER_CGROUP);
+        let result = fs::create_dir_all(&launcher_cgroup)
+            .and_then(|_| {
+                fs::write(
+                    launcher_cgroup.join("cgroup.procs"),
+                    process::id().to_string(),
+                )
+            })
+            .and_then(|_| fs::write(root.join("cgroup.subtree_control"), CONTROLLERS));
+        if let Err(e) = result {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::CgroupSetupError,
+                format!(
+                    "unable to set up the cgroup subtree {:?} error: {}",
+                    root, e
+                ),
+            ));
+        }
+        info!("components are placed in cgroups under {:?}", root);
+        Ok(CgroupTree { root })
+    }
+
+    /// Creates the cgroup of the component and writes its limits, giving
+    /// its `cgroup.procs` for the child to move itself into
+    fn prepare(&self, name: &str, limits: &Limits) -> Result<File> {
+        let path = self.root.join(name);
+        // limits dropped from the settings go back to the defaults
+        let memory_max = match limits.memory_max {
+            0 => "max".to_string(),
+            bytes => bytes.to_string(),
+        };
+        let cpu_weight = match limits.cpu_weight {
+            0 => 100,
+            weight => weight,
+        };
+
+        let result = fs::create_dir_all(&path)
+            .and_then(|_| fs::write(path.join("memory.max"), memory_max))
+            .and_then(|_| fs::write(path.join("cpu.weight"), cpu_weight.to_string()))
+            .and_then(|_| {
+                OpenOptions::new()
+                    .write(true)
+                    .open(path.join("cgroup.procs"))
+            });
+        match result {
+            Ok(procs) => Ok(procs),
+            Err(e) => {
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::CgroupSetupError,
+                    format!("unable to set up the cgroup {:?} error: {}", path, e),
+                ));
+            }
+        }
+    }
+}
+
+/// The cgroup v2 of the launcher from `/proc/self/cgroup`
+fn own_cgroup() -> Result<PathBuf> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    match cgroups.lines().find_map(|line| line.strip_prefix("0::")) {
+        Some(path) => Ok(PathBuf::from(CGROUP_MOUNT).join(path.trim_start_matches('/'))),
+        None => {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::CgroupSetupError,
+                "the launcher is not in a cgroup v2 hierarchy".to_string(),
+            ));
+        }
+    }
+}
+
+/// # Apply Limits
+///
+/// Has the child move itself to its cgroup, set its niceness and OOM
+/// score and drop privileges between fork and exec. Everything that
+/// allocates is done here up front, the child only makes system calls
+pub fn apply_limits(
+    command: &mut Command,
+    name: &str,
+    limits: &Limits,
+    cgroups: Option<&CgroupTree>,
+) -> Result<()> {
+    check_limits(limits)?;
+    let cgroup_procs = match cgroups {
+        Some(cgroups) => Some(cgroups.prepare(name, limits)?),
+        None => None,
+    };
+    let nice = limits.nice;
+    let oom_score_adj = match limits.oom_score_adj {
+        0 => None,
+        score => Some(score.to_string()),
+    };
+    let no_new_privs = limits.no_new_privs || limits.seccomp;
+    let filter = match limits.seccomp {
+        true => Some(seccomp_filter()?),
+        false => None,
+    };
+
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(procs) = &cgroup_procs {
+                // writing 0 moves the writing process
+                if libc::write(procs.as_raw_fd(), b"0".as_ptr().cast(), 1) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if nice != 0 && libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if let Some(score) = &oom_score_adj {
+                write_oom_score_adj(score)?;
+            }
+            if no_new_privs {
+                let zero: libc::c_ulong = 0;
+                if libc::prctl(
+                    libc::PR_SET_NO_NEW_PRIVS,
+                    1 as libc::c_ulong,
+                    zero,
+                    zero,
+                    zero,
+                ) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            if let Some(filter) = &filter {
+                let program = libc::sock_fprog {
+                    len: filter.len() as libc::c_ushort,
+                    filter: filter.as_ptr() as *mut libc::sock_filter,
+                };
+                let result = libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER as libc::c_ulong,
+                    &program as *const libc::sock_fprog,
+                );
+                if result != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+fn check_limits(limits: &Limits) -> Result<()> {
+    let problem = if limits.cpu_weight > 10000 {
+        Some(format!(
+            "cpu_weight {} is not within 1 to 10000",
+            limits.cpu_weight
+        ))
+    } else if !(-20..=19).contains(&limits.nice) {
+        Some(format!("nice {} is not within -20 to 19", limits.nice))
+    } else if !(-1000..=1000).contains(&limits.oom_score_adj) {
+        Some(format!(
+            "oom_score_adj {} is not within -1000 to 1000",
+            limits.oom_score_adj
+        ))
+    } else {
+        None
+    };
+    if let Some(problem) = problem {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::InvalidLimits,
+            problem
+        ));
+    }
+    Ok(())
+}
+
+/// Only async-signal-safe calls, it runs in the forked child
+fn write_oom_score_adj(score: &str) -> io::Result<()> {
+    let fd = unsafe {
+        libc::open(
+            c"/proc/self/oom_score_adj".as_ptr(),
+            libc::O_WRONLY | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let written = unsafe { libc::write(fd, score.as_ptr().cast(), score.len()) };
+    let error = io::Error::last_os_error();
+    unsafe { libc::close(fd) };
+    match written < 0 {
+        true => Err(error),
+        false => Ok(()),
+    }
+}
+
+/// # Seccomp Filter
+///
+/// A BPF program failing the denied system calls with EPERM,
+/// calls made for another architecture kill the process
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn seccomp_filter() -> Result<Vec<libc::sock_filter>> {
+    let statement = |code: u32, k: u32| libc::sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump = |code: u32, k: u32, jt: u8, jf: u8| libc::sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    };
+    let load = |offset: usize| statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset as u32);
+    let deny = statement(
+        libc::BPF_RET | libc::BPF_K,
+        libc::SECCOMP_RET_ERRNO | (libc::EPERM as u32 & libc::SECCOMP_RET_DATA),
+    );
+
+    let mut filter = vec![
+        load(offset_of!(libc::seccomp_data, arch)),
+        jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            AUDIT_ARCH,
+            1,
+            0,
+        ),
+        statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        load(offset_of!(libc::seccomp_data, nr)),
+    ];
+    // x32 calls share the architecture, they would get around the list
+    #[cfg(target_arch = "x86_64")]
+    filter.extend([
+        jump(
+            libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K,
+            0x4000_0000,
+            0,
+            1,
+        ),
+        deny,
+    ]);
+    for syscall in DENIED_SYSCALLS {
+        filter.push(jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            *syscall as u32,
+            0,
+            1,
+        ));
+        filter.push(deny);
+    }
+    filter.push(statement(
+        libc::BPF_RET | libc::BPF_K,
+        libc::SECCOMP_RET_ALLOW,
+    ));
+    Ok(filter)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn seccomp_filter() -> Result<Vec<libc::sock_filter>> {
+    bail!(LauncherError::new(
+        LauncherErrorCodes::InvalidLimits,
+        "seccomp is not supported on this architecture".to_string(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_within_range_are_accepted() {
+        let limits = Limits {
+            cpu_weight: 10000,
+            nice: -20,
+            oom_score_adj: 1000,
+            ..Default::default()
+        };
+
+        assert!(check_limits(&limits).is_ok());
+        assert!(check_limits(&Limits::default()).is_ok());
+    }
+
+    #[test]
+    fn limits_out_of_range_are_rejected() {
+        let out_of_range = [
+            Limits {
+                cpu_weight: 10001,
+                ..Default::default()
+            },
+            Limits {
+                nice: 20,
+                ..Default::default()
+            },
+            Limits {
+                oom_score_adj: -1001,
+                ..Default::default()
+            },
+        ];
+
+        for limits in out_of_range {
+            assert!(check_limits(&limits).is_err(), "{:?}", limits);
+        }
+    }
+}
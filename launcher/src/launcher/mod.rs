@@ -0,0 +1,1076 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::mem;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::bail;
+use anyhow::Result;
+use mecha_launcher::protocol::{ComponentStatus, LogEntry, Request, Response};
+use tracing::{error, info};
+
+use crate::control;
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::{self, Component, LauncherSettings, Readiness};
+
+mod dependencies;
+use dependencies::start_order;
+mod limits;
+use limits::{apply_limits, CgroupTree};
+mod output;
+use output::{capture, log_path, OutputLog};
+mod readiness;
+use readiness::{listen_notify, ReadinessProbe};
+mod reload;
+use reload::{diff, watch_settings, Change};
+mod restarts;
+use restarts::{should_restart, RestartTracker};
+mod signals;
+use signals::{open_pidfd, send_signal, unblock_in_child, watch_signals};
+
+/// Longest the supervision loop sleeps when no restart is due
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often socket and bus name readiness is checked
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Log entries kept for every component
+const LOG_LENGTH: usize = 200;
+
+/// Events the supervision loop wakes up for
+#[derive(Debug)]
+pub enum Event {
+    ChildExited {
+        name: String,
+        pid: u32,
+        status: std::io::Result<ExitStatus>,
+    },
+    Control {
+        request: Request,
+        reply: Sender<Response>,
+    },
+    Ready {
+        name: String,
+    },
+    SettingsChanged,
+    Shutdown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentState {
+    // Started once its dependencies are ready
+    Waiting,
+    Running {
+        pid: u32,
+        started_at: Instant,
+        is_ready: bool,
+        // Killed when no ping arrives by then, None without a watchdog
+        watchdog_at: Option<Instant>,
+    },
+    Restarting { at: Instant },
+    // SIGTERM was sent, SIGKILL follows at `kill_at` unless it exits first
+    Stopping {
+        pid: u32,
+        kill_at: Option<Instant>,
+        then_start: bool,
+    },
+    Exited(ExitStatus),
+    Stopped,
+    Failed(String),
+}
+
+impl ComponentState {
+    fn name(&self) -> String {
+        match self {
+            ComponentState::Waiting => "waiting".to_string(),
+            ComponentState::Running { is_ready: true, .. } => "running".to_string(),
+            ComponentState::Running { is_ready: false, .. } => "starting".to_string(),
+            ComponentState::Restarting { .. } => "restarting".to_string(),
+            ComponentState::Stopping { .. } => "stopping".to_string(),
+            ComponentState::Exited(status) => match status.code() {
+                Some(code) => format!("exited({})", code),
+                None => "exited".to_string(),
+            },
+            ComponentState::Stopped => "stopped".to_string(),
+            ComponentState::Failed(_) => "failed".to_string(),
+        }
+    }
+}
+
+struct SupervisedComponent {
+    settings: Component,
+    state: ComponentState,
+    restarts: RestartTracker,
+    log: VecDeque<LogEntry>,
+    // Of the running or stopping instance, signals go through it
+    pidfd: Option<OwnedFd>,
+}
+
+impl SupervisedComponent {
+    fn new(settings: Component) -> Self {
+        Self {
+            settings,
+            state: ComponentState::Stopped,
+            restarts: RestartTracker::default(),
+            log: VecDeque::new(),
+            pidfd: None,
+        }
+    }
+
+    /// Keeps what happened to the component for `mecha-launcherctl logs`
+    fn log(&mut self, message: String) {
+        if self.log.len() == LOG_LENGTH {
+            self.log.pop_front();
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default();
+        self.log.push_back(LogEntry { timestamp, message });
+    }
+
+    fn status(&self, name: &str) -> ComponentStatus {
+        let (pid, uptime) = match self.state {
+            ComponentState::Running {
+                pid, started_at, ..
+            } => {
+                (Some(pid), Some(started_at.elapsed().as_secs()))
+            }
+            ComponentState::Stopping { pid, .. } => (Some(pid), None),
+            _ => (None, None),
+        };
+        let error = match &self.state {
+            ComponentState::Failed(reason) => Some(reason.clone()),
+            _ => None,
+        };
+        ComponentStatus {
+            name: name.to_string(),
+            state: self.state.name(),
+            pid,
+            uptime,
+            restarts: self.restarts.count,
+            error,
+        }
+    }
+}
+
+/// # Launcher
+///
+/// Starts the shell components and supervises them, every child
+/// has a thread blocked on its exit that reports back to the loop
+pub struct Launcher {
+    settings: LauncherSettings,
+    components: HashMap<String, SupervisedComponent>,
+    events: Receiver<Event>,
+    event_sender: Sender<Event>,
+    start_order: Vec<String>,
+    readiness_probe: ReadinessProbe,
+    notify_sockets: HashMap<String, PathBuf>,
+    cgroups: Option<CgroupTree>,
+    is_shutting_down: bool,
+}
+
+impl Launcher {
+    pub fn new(settings: LauncherSettings) -> Launcher {
+        let (event_sender, events) = mpsc::channel();
+        Launcher {
+            settings,
+            components: HashMap::new(),
+            events,
+            event_sender,
+            start_order: vec![],
+            readiness_probe: ReadinessProbe::default(),
+            notify_sockets: HashMap::new(),
+            cgroups: None,
+            is_shutting_down: false,
+        }
+    }
+
+    /// # Run
+    ///
+    /// Starts the enabled components once their dependencies are ready,
+    /// then supervises them until the launcher gets SIGTERM or SIGINT
+    pub fn run(&mut self) -> Result<()> {
+        watch_signals(self.event_sender.clone())?;
+        self.start_order = validate_components(&self.settings.components)?;
+        if self.settings.control.enabled {
+            control::serve(
+                &self.settings.control.socket_path(),
+                self.event_sender.clone(),
+            )?;
+        }
+        // the launcher runs on without reloads when the settings can not be watched
+        let _ = watch_settings(&settings::settings_file_path(), self.event_sender.clone());
+        // as well as without memory and cpu limits when the cgroups can not be set up
+        if self.settings.cgroups.enabled {
+            self.cgroups = CgroupTree::open(&self.settings.cgroups).ok();
+        }
+
+        for (name, component) in self.settings.components.clone() {
+            if !component.enabled {
+                continue;
+            }
+            let mut component = SupervisedComponent::new(component);
+            component.state = match check_executable(&component.settings.bin_path) {
+                Ok(()) => ComponentState::Waiting,
+                Err(e) => {
+                    let reason = LauncherError::new(
+                        LauncherErrorCodes::ComponentNotExecutable,
+                        format!("{} can not be started, {}", name, e),
+                    )
+                    .message;
+                    component.log(format!("failed: {}", reason));
+                    ComponentState::Failed(reason)
+                }
+            };
+            self.components.insert(name, component);
+        }
+        self.start_waiting_apps();
+
+        while !self.is_shutting_down {
+            self.supervise_once()?;
+        }
+        self.shutdown()
+    }
+
+    /// Waits for the next event or deadline and handles it
+    fn supervise_once(&mut self) -> Result<()> {
+        let timeout = match self.next_deadline() {
+            Some(at) => at.saturating_duration_since(Instant::now()),
+            None => IDLE_TIMEOUT,
+        };
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => self.handle_event(event),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => {
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::EventChannelClosed,
+                    "supervision event channel closed".to_string(),
+                ));
+            }
+        }
+        self.kill_overdue_apps();
+        self.kill_hung_apps();
+        self.check_readiness();
+        if !self.is_shutting_down {
+            self.restart_due_apps();
+            self.start_waiting_apps();
+        }
+        Ok(())
+    }
+
+    /// # Shutdown
+    ///
+    /// Stops the components one at a time, dependents
+    /// before the components they depend on
+    fn shutdown(&mut self) -> Result<()> {
+        info!("stopping all components");
+        let mut names = self.start_order.clone();
+        names.reverse();
+
+        for name in names {
+            self.stop_app(&name);
+            while self.is_stopping(&name) {
+                self.supervise_once()?;
+            }
+        }
+        if self.settings.control.enabled {
+            let _ = fs::remove_file(self.settings.control.socket_path());
+        }
+        for socket_path in self.notify_sockets.values() {
+            let _ = fs::remove_file(socket_path);
+        }
+        info!("all components stopped");
+        Ok(())
+    }
+
+    pub fn start_app(&mut self, name: &str) {
+        let event_sender = self.event_sender.clone();
+        let is_notify = matches!(
+            self.components.get(name).map(|component| &component.settings.readiness),
+            Some(Readiness::Notify)
+        );
+        if is_notify && !self.notify_sockets.contains_key(name) {
+            if let Ok(socket_path) = listen_notify(name, event_sender.clone()) {
+                self.notify_sockets.insert(name.to_string(), socket_path);
+            }
+        }
+        let Some(component) = self.components.get_mut(name) else {
+            return;
+        };
+        match component.state {
+            ComponentState::Running { .. } => {
+                info!("{} is already running.", name);
+                return;
+            }
+            ComponentState::Stopping { .. } => {
+                info!("{} is still stopping.", name);
+                return;
+            }
+            _ => (),
+        }
+
+        let settings = &component.settings;
+        let mut command = Command::new(&settings.bin_path);
+        if !settings.settings_path.is_empty() {
+            command.arg("--settings").arg(&settings.settings_path);
+        }
+        command.args(&settings.args).envs(&settings.env);
+        if !settings.working_dir.is_empty() {
+            command.current_dir(&settings.working_dir);
+        }
+        if let Some(socket_path) = self.notify_sockets.get(name) {
+            command.env("NOTIFY_SOCKET", socket_path);
+        }
+        // what a component needs to ping its watchdog
+        command.env("MECHA_LAUNCHER_COMPONENT", name);
+        if self.settings.control.enabled {
+            command.env("MECHA_LAUNCHER_SOCKET", self.settings.control.socket_path());
+        }
+        unblock_in_child(&mut command);
+        let limits_result =
+            apply_limits(&mut command, name, &settings.limits, self.cgroups.as_ref());
+        if let Err(e) = limits_result {
+            let reason = match e.downcast::<LauncherError>() {
+                Ok(e) => e.message,
+                Err(e) => e.to_string(),
+            };
+            component.log(format!("failed: {}", reason));
+            component.state = ComponentState::Failed(reason);
+            return;
+        }
+
+        // without a log the output goes where the launcher's goes
+        let output_log = match self.settings.logs.capture {
+            true => OutputLog::open(name, &self.settings.logs).ok().map(Arc::new),
+            false => None,
+        };
+        if output_log.is_some() {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let reason = LauncherError::new(
+                    LauncherErrorCodes::ComponentSpawnError,
+                    format!("unable to start {} ({}) error: {}", name, settings.bin_path, e),
+                )
+                .message;
+                component.log(format!("failed: {}", reason));
+                component.state = ComponentState::Failed(reason);
+                return;
+            }
+        };
+
+        if let Some(output_log) = output_log {
+            capture(&mut child, output_log);
+        }
+
+        // opened before the wait thread can reap the child
+        let pid = child.id();
+        component.pidfd = open_pidfd(pid);
+        info!("{} started with pid {}", name, pid);
+        component.log(format!("started with pid {}", pid));
+        component.state = ComponentState::Running {
+            pid,
+            started_at: Instant::now(),
+            is_ready: component.settings.readiness == Readiness::Immediate,
+            watchdog_at: watchdog_deadline(&component.settings),
+        };
+        watch_child(name, child, event_sender);
+    }
+
+    /// # Stop App
+    ///
+    /// Asks the component to exit with SIGTERM, it is killed if it
+    /// is still running once the stop timeout passes
+    pub fn stop_app(&mut self, name: &str) {
+        let stop_timeout = Duration::from_millis(self.settings.supervisor.stop_timeout);
+        let Some(component) = self.components.get_mut(name) else {
+            return;
+        };
+        let pid = match component.state {
+            ComponentState::Running { pid, .. } => pid,
+            ComponentState::Stopping {
+                ref mut then_start, ..
+            } => {
+                *then_start = false;
+                return;
+            }
+            _ => {
+                component.state = ComponentState::Stopped;
+                return;
+            }
+        };
+
+        // stopping components are not restarted once they exit
+        component.state = ComponentState::Stopping {
+            pid,
+            kill_at: Some(Instant::now() + stop_timeout),
+            then_start: false,
+        };
+        component.log(format!("stopping pid {}", pid));
+        info!("stopping {} (pid {})", name, pid);
+        send_signal(name, pid, component.pidfd.as_ref(), libc::SIGTERM);
+    }
+
+    fn is_stopping(&self, name: &str) -> bool {
+        matches!(
+            self.components.get(name).map(|component| &component.state),
+            Some(ComponentState::Stopping { .. })
+        )
+    }
+
+    pub fn list_running_apps(&self) {
+        info!("Running Apps:");
+        for (name, component) in &self.components {
+            if let ComponentState::Running { pid, .. } = component.state {
+                info!("{} (pid {})", name, pid);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::ChildExited { name, pid, status } => self.handle_exit(&name, pid, status),
+            Event::Control { request, reply } => {
+                let _ = reply.send(self.handle_request(request));
+            }
+            Event::Ready { name } => self.set_ready(&name),
+            Event::SettingsChanged => self.reload_settings(),
+            Event::Shutdown => self.is_shutting_down = true,
+        }
+    }
+
+    fn set_ready(&mut self, name: &str) {
+        let Some(component) = self.components.get_mut(name) else {
+            return;
+        };
+        if let ComponentState::Running {
+            ref mut is_ready, ..
+        } = component.state
+        {
+            if !*is_ready {
+                *is_ready = true;
+                info!("{} is ready", name);
+                component.log("ready".to_string());
+            }
+        }
+    }
+
+    /// Polls the components that tell their readiness by a socket or bus name
+    fn check_readiness(&mut self) {
+        let mut ready_apps = vec![];
+        for (name, component) in self.components.iter() {
+            if let ComponentState::Running {
+                is_ready: false, ..
+            } = component.state
+            {
+                if self.readiness_probe.is_ready(&component.settings.readiness) {
+                    ready_apps.push(name.clone());
+                }
+            }
+        }
+        for name in ready_apps {
+            self.set_ready(&name);
+        }
+    }
+
+    /// Whether the `requires` dependencies of the component are ready, and
+    /// its `after` dependencies are ready, timed out or not running at all
+    fn dependencies_met(&self, component: &Component) -> bool {
+        let ready_timeout = Duration::from_millis(self.settings.supervisor.ready_timeout);
+        let state_of = |name: &String| {
+            self.components
+                .get(name)
+                .map(|component| &component.state)
+        };
+
+        let after_met = component.after.iter().all(|name| match state_of(name) {
+            Some(ComponentState::Running {
+                is_ready,
+                started_at,
+                ..
+            }) => *is_ready || started_at.elapsed() >= ready_timeout,
+            Some(ComponentState::Waiting)
+            | Some(ComponentState::Restarting { .. })
+            | Some(ComponentState::Stopping { .. }) => false,
+            _ => true,
+        });
+        let requires_met = component.requires.iter().all(|name| {
+            matches!(
+                state_of(name),
+                Some(ComponentState::Running { is_ready: true, .. })
+            )
+        });
+        after_met && requires_met
+    }
+
+    /// A required component that failed, the dependent would wait for it forever
+    fn failed_requirement(&self, component: &Component) -> Option<String> {
+        component
+            .requires
+            .iter()
+            .find(|name| {
+                matches!(
+                    self.components.get(*name).map(|component| &component.state),
+                    Some(ComponentState::Failed(_))
+                )
+            })
+            .cloned()
+    }
+
+    fn fail_waiting_app(&mut self, name: &str, requirement: &str) {
+        let reason = LauncherError::new(
+            LauncherErrorCodes::DependencyFailed,
+            format!("{} requires {}, which failed", name, requirement),
+        )
+        .message;
+        if let Some(component) = self.components.get_mut(name) {
+            component.log(format!("failed: {}", reason));
+            component.state = ComponentState::Failed(reason);
+        }
+    }
+
+    /// Starts the waiting components whose dependencies are met, in start order
+    fn start_waiting_apps(&mut self) {
+        let mut has_started_apps = false;
+        for name in self.start_order.clone() {
+            let Some(component) = self.components.get(&name) else {
+                continue;
+            };
+            if component.state != ComponentState::Waiting {
+                continue;
+            }
+            if let Some(requirement) = self.failed_requirement(&component.settings) {
+                self.fail_waiting_app(&name, &requirement);
+                continue;
+            }
+            if !self.dependencies_met(&component.settings) {
+                continue;
+            }
+
+            for dependency_name in &component.settings.after {
+                let dependency = self.components.get(dependency_name);
+                if let Some(ComponentState::Running {
+                    is_ready: false, ..
+                }) = dependency.map(|dependency| &dependency.state)
+                {
+                    LauncherError::new(
+                        LauncherErrorCodes::ComponentNotReady,
+                        format!(
+                            "{} is not ready after {}ms, starting {} without it",
+                            dependency_name, self.settings.supervisor.ready_timeout, name
+                        ),
+                    );
+                }
+            }
+            self.start_app(&name);
+            has_started_apps = true;
+        }
+
+        if has_started_apps {
+            self.list_running_apps();
+        }
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        if self.is_shutting_down {
+            return Response::Error {
+                message: "launcher is shutting down".to_string(),
+            };
+        }
+        let name = match &request {
+            Request::Status => return self.status(),
+            Request::Start { component }
+            | Request::Stop { component }
+            | Request::Restart { component }
+            | Request::Logs { component, .. }
+            | Request::Ping { component } => component.clone(),
+        };
+        if !self.settings.components.contains_key(&name) {
+            return Response::Error {
+                message: format!("unknown component {}", name),
+            };
+        }
+
+        match request {
+            Request::Status => self.status(),
+            Request::Start { .. } => self.start_requested(&name),
+            Request::Stop { .. } => {
+                self.stop_app(&name);
+                Response::Ok
+            }
+            // the component is started again once it has exited
+            Request::Restart { .. } => {
+                self.stop_app(&name);
+                self.start_requested(&name)
+            }
+            Request::Logs { lines, .. } => {
+                let entries = match self.components.get(&name) {
+                    Some(component) => {
+                        let skipped = component.log.len().saturating_sub(lines);
+                        component.log.iter().skip(skipped).cloned().collect()
+                    }
+                    None => vec![],
+                };
+                let output = match self.settings.logs.capture {
+                    true => Some(log_path(&name, &self.settings.logs)),
+                    false => None,
+                };
+                Response::Logs { entries, output }
+            }
+            Request::Ping { .. } => self.ping(&name),
+        }
+    }
+
+    /// Moves the watchdog deadline of the component on
+    fn ping(&mut self, name: &str) -> Response {
+        let Some(component) = self.components.get_mut(name) else {
+            return Response::Error {
+                message: format!("{} is not running", name),
+            };
+        };
+        let deadline = watchdog_deadline(&component.settings);
+        match component.state {
+            ComponentState::Running {
+                ref mut watchdog_at,
+                ..
+            } => {
+                *watchdog_at = deadline;
+                Response::Ok
+            }
+            _ => Response::Error {
+                message: format!("{} is not running", name),
+            },
+        }
+    }
+
+    /// Status of every configured component, disabled ones included
+    fn status(&self) -> Response {
+        let components = self
+            .settings
+            .components
+            .keys()
+            .map(|name| match self.components.get(name) {
+                Some(component) => component.status(name),
+                None => ComponentStatus {
+                    name: name.clone(),
+                    state: "disabled".to_string(),
+                    pid: None,
+                    uptime: None,
+                    restarts: 0,
+                    error: None,
+                },
+            })
+            .collect();
+        Response::Status { components }
+    }
+
+    /// Starts a component on request once its dependencies are met, failed
+    /// components get a fresh restart budget and disabled ones are started
+    /// all the same
+    fn start_requested(&mut self, name: &str) -> Response {
+        if !self.components.contains_key(name) {
+            let Some(settings) = self.settings.components.get(name) else {
+                return Response::Error {
+                    message: format!("{} is not configured", name),
+                };
+            };
+            self.components
+                .insert(name.to_string(), SupervisedComponent::new(settings.clone()));
+        }
+        if let Some(component) = self.components.get_mut(name) {
+            component.restarts.reset();
+        }
+        self.request_start(name);
+        Response::Ok
+    }
+
+    /// Has the component started once its dependencies are met,
+    /// or once it exited when it is still stopping
+    fn request_start(&mut self, name: &str) {
+        let Some(component) = self.components.get_mut(name) else {
+            return;
+        };
+        match component.state {
+            ComponentState::Running { .. } => (),
+            ComponentState::Stopping {
+                ref mut then_start, ..
+            } => *then_start = true,
+            _ => component.state = ComponentState::Waiting,
+        }
+    }
+
+    /// # Reload Settings
+    ///
+    /// Reads the settings again and applies what changed, components
+    /// whose settings are the same are left running untouched
+    fn reload_settings(&mut self) {
+        // a settings file that does not read or parse was already logged
+        let Ok(settings) = settings::read_settings_yml() else {
+            info!("keeping the current settings");
+            return;
+        };
+        let Ok(start_order) = validate_components(&settings.components) else {
+            info!("keeping the current settings");
+            return;
+        };
+
+        let changes = diff(&self.settings.components, &settings.components);
+        let old_settings = mem::replace(&mut self.settings, settings);
+        self.start_order = start_order;
+        if old_settings.control != self.settings.control {
+            info!("control settings changed, restart the launcher to apply them");
+            self.settings.control = old_settings.control;
+        }
+        if old_settings.cgroups != self.settings.cgroups {
+            info!("cgroup settings changed, restart the launcher to apply them");
+            self.settings.cgroups = old_settings.cgroups;
+        }
+
+        for (name, change) in changes {
+            info!("settings of {} changed, {:?}", name, change);
+            let settings = self.settings.components.get(&name).cloned();
+            match (self.components.get_mut(&name), settings.clone()) {
+                (Some(component), Some(settings)) => component.settings = settings,
+                (None, Some(settings)) if change == Change::Start => {
+                    self.components
+                        .insert(name.clone(), SupervisedComponent::new(settings));
+                }
+                _ => (),
+            }
+
+            match change {
+                Change::Start => self.request_start(&name),
+                Change::Stop => self.stop_app(&name),
+                Change::Restart => {
+                    self.stop_app(&name);
+                    self.request_start(&name);
+                }
+                Change::Update => (),
+            }
+
+            if settings.is_none() && !self.is_stopping(&name) {
+                self.components.remove(&name);
+            }
+        }
+    }
+
+    fn handle_exit(&mut self, name: &str, pid: u32, status: std::io::Result<ExitStatus>) {
+        let supervisor_settings = self.settings.supervisor.clone();
+        let Some(component) = self.components.get_mut(name) else {
+            return;
+        };
+
+        // exits of an earlier instance change nothing
+        let started_at = match component.state {
+            ComponentState::Running {
+                pid: running_pid,
+                started_at,
+                ..
+            } if running_pid == pid => {
+                component.pidfd = None;
+                started_at
+            }
+            ComponentState::Stopping {
+                pid: stopping_pid,
+                then_start,
+                ..
+            } if stopping_pid == pid => {
+                component.pidfd = None;
+                info!("{} (pid {}) has stopped.", name, pid);
+                component.log(format!("pid {} stopped", pid));
+                component.state = match then_start {
+                    true => ComponentState::Waiting,
+                    false => ComponentState::Stopped,
+                };
+                // components removed from the settings are forgotten once stopped
+                if !self.settings.components.contains_key(name) {
+                    self.components.remove(name);
+                }
+                return;
+            }
+            _ => {
+                info!("{} (pid {}) has exited.", name, pid);
+                return;
+            }
+        };
+
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                error!("Error checking {} status: {}", name, e);
+                component.log(format!("failed: {}", e));
+                component.state = ComponentState::Failed(e.to_string());
+                return;
+            }
+        };
+        info!("{} (pid {}) has exited with {}.", name, pid, status);
+        component.log(format!("pid {} exited with {}", pid, status));
+
+        if self.is_shutting_down || !should_restart(component.settings.restart, &status) {
+            component.state = ComponentState::Exited(status);
+            return;
+        }
+
+        let now = Instant::now();
+        match component.restarts.next_delay(
+            now,
+            now.duration_since(started_at),
+            &supervisor_settings,
+        ) {
+            Some(delay) => {
+                info!("restarting {} in {:?}", name, delay);
+                component.log(format!("restarting in {:?}", delay));
+                component.state = ComponentState::Restarting { at: now + delay };
+            }
+            None => {
+                let reason = format!(
+                    "crash looping, restarted {} times within {}s",
+                    supervisor_settings.max_restarts, supervisor_settings.restart_window
+                );
+                LauncherError::new(
+                    LauncherErrorCodes::ComponentCrashLoop,
+                    format!("{} is {}, giving up", name, reason),
+                );
+                component.log(format!("failed: {}", reason));
+                component.state = ComponentState::Failed(reason);
+            }
+        }
+    }
+
+    /// Earliest due restart, kill, readiness check, readiness timeout or watchdog
+    fn next_deadline(&self) -> Option<Instant> {
+        let now = Instant::now();
+        let ready_timeout = Duration::from_millis(self.settings.supervisor.ready_timeout);
+        self.components
+            .values()
+            .filter_map(|component| match component.state {
+                ComponentState::Restarting { at } => Some(at),
+                ComponentState::Stopping { kill_at, .. } => kill_at,
+                ComponentState::Running {
+                    is_ready: false,
+                    started_at,
+                    watchdog_at,
+                    ..
+                } => {
+                    let timeout_at = Some(started_at + ready_timeout).filter(|at| *at > now);
+                    let poll_at = match component.settings.readiness {
+                        Readiness::Socket { .. } | Readiness::DbusName { .. } => {
+                            Some(now + READINESS_POLL_INTERVAL)
+                        }
+                        _ => None,
+                    };
+                    timeout_at
+                        .into_iter()
+                        .chain(poll_at)
+                        .chain(watchdog_at)
+                        .min()
+                }
+                ComponentState::Running { watchdog_at, .. } => watchdog_at,
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Kills the components that did not exit within the stop timeout
+    fn kill_overdue_apps(&mut self) {
+        let now = Instant::now();
+        for (name, component) in self.components.iter_mut() {
+            let ComponentState::Stopping {
+                pid,
+                ref mut kill_at,
+                ..
+            } = component.state
+            else {
+                continue;
+            };
+            if !kill_at.is_some_and(|kill_at| kill_at <= now) {
+                continue;
+            }
+            // the wait thread reaps it and reports the exit
+            *kill_at = None;
+            info!("{} (pid {}) did not stop in time, killing it", name, pid);
+            component.log(format!("pid {} did not stop in time, killing it", pid));
+            send_signal(name, pid, component.pidfd.as_ref(), libc::SIGKILL);
+        }
+    }
+
+    /// # Kill Hung Apps
+    ///
+    /// Kills the components that missed their watchdog ping, the exit
+    /// is then handled as any crash so the restart policy applies
+    fn kill_hung_apps(&mut self) {
+        let now = Instant::now();
+        for (name, component) in self.components.iter_mut() {
+            let ComponentState::Running {
+                pid,
+                ref mut watchdog_at,
+                ..
+            } = component.state
+            else {
+                continue;
+            };
+            // watchdogs turned off by a reload no longer count
+            if component.settings.watchdog == 0 {
+                *watchdog_at = None;
+                continue;
+            }
+            if !watchdog_at.is_some_and(|watchdog_at| watchdog_at <= now) {
+                continue;
+            }
+            // the wait thread reaps it and reports the exit
+            *watchdog_at = None;
+            LauncherError::new(
+                LauncherErrorCodes::WatchdogTimeout,
+                format!(
+                    "{} (pid {}) sent no watchdog ping for {}s, killing it",
+                    name, pid, component.settings.watchdog
+                ),
+            );
+            component.log(format!(
+                "no watchdog ping for {}s, killing pid {}",
+                component.settings.watchdog, pid
+            ));
+            send_signal(name, pid, component.pidfd.as_ref(), libc::SIGKILL);
+        }
+    }
+
+    fn restart_due_apps(&mut self) {
+        let now = Instant::now();
+        let due_apps: Vec<String> = self
+            .components
+            .iter()
+            .filter_map(|(name, component)| match component.state {
+                ComponentState::Restarting { at } if at <= now => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for name in due_apps {
+            self.start_app(&name);
+        }
+    }
+}
+
+/// When the next watchdog ping of the component is due
+fn watchdog_deadline(component: &Component) -> Option<Instant> {
+    match component.watchdog {
+        0 => None,
+        seconds => Some(Instant::now() + Duration::from_secs(seconds)),
+    }
+}
+
+/// Checks the component names and dependencies, giving the start order
+fn validate_components(components: &BTreeMap<String, Component>) -> Result<Vec<String>> {
+    for name in components.keys() {
+        if !is_valid_name(name) {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::InvalidComponentName,
+                format!("component name {:?} may only use letters, digits, - and _", name),
+            ));
+        }
+    }
+    start_order(components)
+}
+
+/// # Check Executable
+///
+/// Whether the binary exists and is executable, bare names
+/// are looked up in `$PATH` as spawning does
+fn check_executable(bin_path: &str) -> std::result::Result<(), String> {
+    if bin_path.is_empty() {
+        return Err("no bin_path is set".to_string());
+    }
+    let candidates: Vec<PathBuf> = match bin_path.contains('/') {
+        true => vec![PathBuf::from(bin_path)],
+        false => env::var("PATH")
+            .unwrap_or_default()
+            .split(':')
+            .map(|directory| Path::new(directory).join(bin_path))
+            .collect(),
+    };
+
+    let is_executable = |path: &PathBuf| {
+        fs::metadata(path)
+            .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+    match candidates.iter().any(is_executable) {
+        true => Ok(()),
+        false if candidates.iter().any(|path| path.exists()) => {
+            Err(format!("{} is not an executable file", bin_path))
+        }
+        false => Err(format!("{} does not exist", bin_path)),
+    }
+}
+
+/// Names end up in socket and thread names, so they are kept to a safe set
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Blocks a thread on the exit of the child and reports it
+fn watch_child(name: &str, mut child: Child, events: Sender<Event>) {
+    let pid = child.id();
+    let exited_name = name.to_string();
+    let spawn_result = thread::Builder::new()
+        .name(format!("wait-{}", pid))
+        .spawn(move || {
+            let status = child.wait();
+            let _ = events.send(Event::ChildExited {
+                name: exited_name,
+                pid,
+                status,
+            });
+        });
+    if let Err(e) = spawn_result {
+        LauncherError::new(
+            LauncherErrorCodes::ChildWaitError,
+            format!("unable to watch {} (pid {}) error: {}", name, pid, e),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn executables_are_found() {
+        assert!(check_executable("/bin/sh").is_ok());
+        assert!(check_executable("sh").is_ok());
+    }
+
+    #[test]
+    fn missing_and_plain_files_are_rejected() {
+        let path = env::temp_dir().join(format!("not-executable-{}", process::id()));
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let plain_file = check_executable(path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(
+            plain_file,
+            Err(format!("{} is not an executable file", path.display()))
+        );
+        assert_eq!(
+            check_executable("/nonexistent/app"),
+            Err("/nonexistent/app does not exist".to_string())
+        );
+        assert_eq!(check_executable(""), Err("no bin_path is set".to_string()));
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    mem,
+    os::unix::{fs::OpenOptionsExt, net::UnixDatagram},
+    path::{Path, PathBuf},
+    process::Child,
+    ptr,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use anyhow::bail;
+use anyhow::Result;
+use tracing::{debug, error};
+
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::LogSettings;
+
+/// Socket of the journald native protocol
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+#[derive(Debug, Clone, Copy)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn name(&self) -> &'static str {
+        match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+
+    /// Syslog priority of the lines, info for stdout and error for stderr
+    fn priority(&self) -> u8 {
+        match self {
+            Stream::Stdout => 6,
+            Stream::Stderr => 3,
+        }
+    }
+}
+
+/// # Output Log
+///
+/// Where the stdout and stderr of a component end up, a file of
+/// its own rotated by size and, when enabled, the journal
+pub struct OutputLog {
+    name: String,
+    file: Mutex<RotatingFile>,
+    journal: Option<UnixDatagram>,
+}
+
+impl OutputLog {
+    pub fn open(name: &str, settings: &LogSettings) -> Result<OutputLog> {
+        let path = log_path(name, settings);
+        let directory = log_directory(settings);
+        if let Err(e) = fs::create_dir_all(&directory) {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::LogOpenError,
+                format!("unable to create log directory {:?} error: {}", directory, e),
+            ));
+        }
+
+        let file = match RotatingFile::open(path.clone(), settings.max_size, settings.max_files) {
+            Ok(file) => file,
+            Err(e) => {
+                bail!(LauncherError::new(
+                    LauncherErrorCodes::LogOpenError,
+                    format!("unable to open log {:?} error: {}", path, e),
+                ));
+            }
+        };
+
+        let journal = match settings.journald {
+            true => connect_journal(),
+            false => None,
+        };
+
+        Ok(OutputLog {
+            name: name.to_string(),
+            file: Mutex::new(file),
+            journal,
+        })
+    }
+
+    fn write_line(&self, stream: Stream, line: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            let line = format!("{} {}: {}\n", timestamp(), stream.name(), line);
+            if let Err(e) = file.write_line(&line) {
+                debug!("unable to write the log of {}: {}", self.name, e);
+            }
+        }
+
+        if let Some(journal) = &self.journal {
+            let entry = format!(
+                "MESSAGE={}\nSYSLOG_IDENTIFIER={}\nPRIORITY={}\n",
+                line,
+                self.name,
+                stream.priority()
+            );
+            let _ = journal.send(entry.as_bytes());
+        }
+    }
+}
+
+/// # Capture
+///
+/// Takes the piped stdout and stderr of the child and copies
+/// them line by line to the log until the child closes them
+pub fn capture(child: &mut Child, log: Arc<OutputLog>) {
+    if let Some(stdout) = child.stdout.take() {
+        copy_lines(stdout, Stream::Stdout, log.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        copy_lines(stderr, Stream::Stderr, log);
+    }
+}
+
+fn copy_lines<R: Read + Send + 'static>(reader: R, stream: Stream, log: Arc<OutputLog>) {
+    let name = log.name.clone();
+    let spawn_result = thread::Builder::new()
+        .name(format!("{}-{}", stream.name(), name))
+        .spawn(move || {
+            let mut reader = BufReader::new(reader);
+            let mut line = vec![];
+            loop {
+                line.clear();
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => {
+                        let text = String::from_utf8_lossy(&line);
+                        log.write_line(stream, text.trim_end_matches(['\n', '\r']));
+                    }
+                }
+            }
+        });
+    if let Err(e) = spawn_result {
+        error!("unable to capture the {} of {}: {}", stream.name(), name, e);
+    }
+}
+
+/// # Log Path
+///
+/// File the output of the component is written to, rotated
+/// logs sit next to it as `name.log.1`, `name.log.2` and on
+pub fn log_path(name: &str, settings: &LogSettings) -> PathBuf {
+    log_directory(settings).join(format!("{}.log", name))
+}
+
+/// The configured directory, else `$XDG_STATE_HOME/mecha-launcher`
+/// or `~/.local/state/mecha-launcher`
+fn log_directory(settings: &LogSettings) -> PathBuf {
+    if !settings.directory.is_empty() {
+        return PathBuf::from(&settings.directory);
+    }
+    let state_dir = match env::var("XDG_STATE_HOME") {
+        Ok(state_dir) => PathBuf::from(state_dir),
+        Err(_) => match env::var("HOME") {
+            Ok(home) => Path::new(&home).join(".local/state"),
+            Err(_) => PathBuf::from("/tmp"),
+        },
+    };
+    state_dir.join("mecha-launcher")
+}
+
+fn connect_journal() -> Option<UnixDatagram> {
+    let journal = UnixDatagram::unbound().and_then(|journal| {
+        journal.connect(JOURNAL_SOCKET)?;
+        Ok(journal)
+    });
+    match journal {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            debug!("unable to connect to the journal: {}", e);
+            None
+        }
+    }
+}
+
+/// Local time as `2024-01-31 13:45:07`
+fn timestamp() -> String {
+    let mut time: libc::tm = unsafe { mem::zeroed() };
+    unsafe {
+        let now = libc::time(ptr::null_mut());
+        libc::localtime_r(&now, &mut time);
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.tm_year + 1900,
+        time.tm_mon + 1,
+        time.tm_mday,
+        time.tm_hour,
+        time.tm_min,
+        time.tm_sec
+    )
+}
+
+/// # Rotating File
+///
+/// Appends to `name.log` and once it would grow past `max_size`
+/// moves it to `name.log.1`, keeping `max_files` older logs
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: u32,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: u32) -> io::Result<RotatingFile> {
+        let file = open_log(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64;
+        if self.max_size > 0 && self.size > 0 && self.size + length > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += length;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..self.max_files).rev() {
+            let _ = fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+        }
+        match self.max_files {
+            0 => fs::remove_file(&self.path)?,
+            _ => fs::rename(&self.path, self.rotated_path(1))?,
+        }
+        self.file = open_log(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+}
+
+/// Output can hold anything the component prints, so
+/// others than the user and its group do not get to read it
+fn open_log(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o640)
+        .open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::fs::PermissionsExt, process};
+
+    #[test]
+    fn log_is_rotated_once_full() {
+        let directory = env::temp_dir().join(format!("rotating-file-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("app.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_line(line).unwrap();
+        }
+        let read = |index: &str| fs::read_to_string(directory.join(format!("app.log{}", index)));
+        let (current, first, second, third) = (read(""), read(".1"), read(".2"), read(".3"));
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(current.unwrap(), "fourth\n");
+        assert_eq!(mode & 0o777, 0o640);
+        assert_eq!(first.unwrap(), "third\n");
+        assert_eq!(second.unwrap(), "second\n");
+        assert!(third.is_err());
+    }
+
+    #[test]
+    fn zero_max_size_never_rotates() {
+        let directory = env::temp_dir().join(format!("unrotated-file-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("app.log");
+        let mut file = RotatingFile::open(path.clone(), 0, 2).unwrap();
+
+        file.write_line("first\n").unwrap();
+        file.write_line("second\n").unwrap();
+        let current = fs::read_to_string(&path);
+        let is_rotated = directory.join("app.log.1").exists();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(current.unwrap(), "first\nsecond\n");
+        assert!(!is_rotated);
+    }
+}
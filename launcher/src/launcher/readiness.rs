@@ -0,0 +1,126 @@
+use std::{
+    env, fs,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::Duration,
+};
+
+use anyhow::bail;
+use anyhow::Result;
+use dbus::blocking::Connection;
+use tracing::debug;
+
+use super::Event;
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::Readiness;
+
+/// Longest a bus name check may block the supervision loop
+const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// # Readiness Probe
+///
+/// Checks the components that tell their readiness by a socket
+/// or a bus name, `Notify` components report it as an event instead
+#[derive(Default)]
+pub struct ReadinessProbe {
+    session_bus: Option<Connection>,
+}
+
+impl ReadinessProbe {
+    pub fn is_ready(&mut self, readiness: &Readiness) -> bool {
+        match readiness {
+            Readiness::Immediate => true,
+            Readiness::Socket { path } => Path::new(path).exists(),
+            Readiness::DbusName { name } => self.has_owner(name),
+            Readiness::Notify => false,
+        }
+    }
+
+    fn has_owner(&mut self, name: &str) -> bool {
+        if self.session_bus.is_none() {
+            self.session_bus = match Connection::new_session() {
+                Ok(bus) => Some(bus),
+                Err(e) => {
+                    debug!("unable to connect to the session bus: {}", e);
+                    None
+                }
+            };
+        }
+        let Some(session_bus) = &self.session_bus else {
+            return false;
+        };
+
+        let proxy = session_bus.with_proxy(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            DBUS_TIMEOUT,
+        );
+        let result: Result<(bool,), dbus::Error> =
+            proxy.method_call("org.freedesktop.DBus", "NameHasOwner", (name,));
+        match result {
+            Ok((has_owner,)) => has_owner,
+            Err(e) => {
+                // connect again on the next check
+                debug!("unable to look up the owner of {}: {}", name, e);
+                self.session_bus = None;
+                false
+            }
+        }
+    }
+}
+
+/// Socket a `Notify` component gets as `$NOTIFY_SOCKET`
+pub fn notify_socket_path(name: &str) -> PathBuf {
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or(String::from("/tmp"));
+    PathBuf::from(runtime_dir).join(format!("mecha-launcher-{}.notify", name))
+}
+
+/// # Listen Notify
+///
+/// Binds the notify socket of the component and reports a
+/// `READY=1` sent to it as a ready event
+pub fn listen_notify(name: &str, events: Sender<Event>) -> Result<PathBuf> {
+    let socket_path = notify_socket_path(name);
+    // a socket left behind by an earlier run would fail the bind
+    let _ = fs::remove_file(&socket_path);
+    let socket = match UnixDatagram::bind(&socket_path) {
+        Ok(socket) => socket,
+        Err(e) => {
+            bail!(LauncherError::new(
+                LauncherErrorCodes::NotifySocketBindError,
+                format!(
+                    "unable to bind notify socket {:?} error: {}",
+                    socket_path, e
+                ),
+            ));
+        }
+    };
+
+    let ready_name = name.to_string();
+    let spawn_result = thread::Builder::new()
+        .name(format!("notify-{}", name))
+        .spawn(move || {
+            let mut buffer = [0; 4096];
+            while let Ok(length) = socket.recv(&mut buffer) {
+                let message = String::from_utf8_lossy(&buffer[..length]);
+                if message.lines().any(|line| line == "READY=1")
+                    && events
+                        .send(Event::Ready {
+                            name: ready_name.clone(),
+                        })
+                        .is_err()
+                {
+                    return;
+                }
+            }
+        });
+    if let Err(e) = spawn_result {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::NotifySocketBindError,
+            format!("unable to start notify thread error: {}", e),
+        ));
+    }
+    Ok(socket_path)
+}
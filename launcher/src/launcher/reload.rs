@@ -0,0 +1,220 @@
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    fs::File,
+    io::Read,
+    mem,
+    os::unix::{ffi::OsStrExt, io::FromRawFd},
+    path::Path,
+    sync::mpsc::Sender,
+    thread,
+};
+
+use anyhow::bail;
+use anyhow::Result;
+use tracing::error;
+
+use super::Event;
+use crate::errors::{LauncherError, LauncherErrorCodes};
+use crate::settings::Component;
+
+/// What a settings change means for a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Start,
+    Stop,
+    Restart,
+    // Takes effect from the next start, or was not running anyway
+    Update,
+}
+
+/// # Diff
+///
+/// Changes between the components of the old and new settings,
+/// components whose settings are the same are left out
+pub fn diff(
+    old: &BTreeMap<String, Component>,
+    new: &BTreeMap<String, Component>,
+) -> Vec<(String, Change)> {
+    let is_enabled = |components: &BTreeMap<String, Component>, name: &String| {
+        components
+            .get(name)
+            .map(|component| component.enabled)
+            .unwrap_or(false)
+    };
+
+    let mut changes = vec![];
+    for name in old.keys().chain(new.keys().filter(|name| !old.contains_key(*name))) {
+        let change = match (is_enabled(old, name), is_enabled(new, name)) {
+            (false, true) => Change::Start,
+            (true, false) => Change::Stop,
+            (true, true) if is_launched_differently(&old[name], &new[name]) => Change::Restart,
+            _ => Change::Update,
+        };
+        if change == Change::Update && are_same(old.get(name), new.get(name)) {
+            continue;
+        }
+        changes.push((name.clone(), change));
+    }
+    changes
+}
+
+/// Whether the running instance no longer matches the settings
+fn is_launched_differently(old: &Component, new: &Component) -> bool {
+    old.bin_path != new.bin_path
+        || old.settings_path != new.settings_path
+        || old.args != new.args
+        || old.env != new.env
+        || old.working_dir != new.working_dir
+        || old.readiness != new.readiness
+        || old.limits != new.limits
+}
+
+fn are_same(old: Option<&Component>, new: Option<&Component>) -> bool {
+    match (old, new) {
+        (Some(old), Some(new)) => {
+            !is_launched_differently(old, new)
+                && old.enabled == new.enabled
+                && old.restart == new.restart
+                && old.after == new.after
+                && old.requires == new.requires
+                && old.watchdog == new.watchdog
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// # Watch Settings
+///
+/// Watches the directory of the settings file with inotify and sends a
+/// settings changed event whenever the file is written or replaced,
+/// editors that save by renaming a new file over it included
+pub fn watch_settings(path: &Path, events: Sender<Event>) -> Result<()> {
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let Some(file_name) = path.file_name().map(|name| name.to_os_string()) else {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SettingsWatchError,
+            format!("settings path {:?} has no file name", path),
+        ));
+    };
+    let Ok(directory_path) = CString::new(directory.as_os_str().as_bytes()) else {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SettingsWatchError,
+            format!("settings directory {:?} is not a valid path", directory),
+        ));
+    };
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SettingsWatchError,
+            format!("unable to start inotify: {}", std::io::Error::last_os_error()),
+        ));
+    }
+    let mut inotify = unsafe { File::from_raw_fd(fd) };
+    let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO;
+    if unsafe { libc::inotify_add_watch(fd, directory_path.as_ptr(), mask) } < 0 {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SettingsWatchError,
+            format!(
+                "unable to watch {:?}: {}",
+                directory,
+                std::io::Error::last_os_error()
+            ),
+        ));
+    }
+
+    let spawn_result = thread::Builder::new()
+        .name("settings-watch".to_string())
+        .spawn(move || {
+            let header_length = mem::size_of::<libc::inotify_event>();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let length = match inotify.read(&mut buffer) {
+                    Ok(length) => length,
+                    Err(e) => {
+                        error!("unable to read settings changes: {}", e);
+                        return;
+                    }
+                };
+
+                let mut is_changed = false;
+                let mut offset = 0;
+                while offset + header_length <= length {
+                    let event: libc::inotify_event = unsafe {
+                        std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const _)
+                    };
+                    let name_start = offset + header_length;
+                    let name_end = (name_start + event.len as usize).min(length);
+                    let name = buffer[name_start..name_end].split(|byte| *byte == 0).next();
+                    is_changed |= name == Some(file_name.as_bytes());
+                    offset = name_end;
+                }
+
+                if is_changed && events.send(Event::SettingsChanged).is_err() {
+                    return;
+                }
+            }
+        });
+    if let Err(e) = spawn_result {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SettingsWatchError,
+            format!("unable to start settings watch thread error: {}", e),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(enabled: bool, bin_path: &str) -> Component {
+        Component {
+            enabled,
+            bin_path: bin_path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn changes_between_settings() {
+        let old = BTreeMap::from([
+            ("disabled".to_string(), component(false, "/bin/a")),
+            ("moved".to_string(), component(true, "/bin/b")),
+            ("removed".to_string(), component(true, "/bin/c")),
+            ("same".to_string(), component(true, "/bin/d")),
+            ("watched".to_string(), component(true, "/bin/e")),
+        ]);
+        let new = BTreeMap::from([
+            ("added".to_string(), component(true, "/bin/f")),
+            ("disabled".to_string(), component(true, "/bin/a")),
+            ("moved".to_string(), component(true, "/usr/bin/b")),
+            ("same".to_string(), component(true, "/bin/d")),
+            (
+                "watched".to_string(),
+                Component {
+                    watchdog: 10,
+                    ..component(true, "/bin/e")
+                },
+            ),
+        ]);
+
+        let changes = diff(&old, &new);
+
+        assert_eq!(
+            changes,
+            [
+                ("disabled".to_string(), Change::Start),
+                ("moved".to_string(), Change::Restart),
+                ("removed".to_string(), Change::Stop),
+                ("watched".to_string(), Change::Update),
+                ("added".to_string(), Change::Start),
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::VecDeque,
+    process::ExitStatus,
+    time::{Duration, Instant},
+};
+
+use crate::settings::{RestartPolicy, SupervisorSettings};
+
+/// Whether an exit calls for a restart under the policy
+pub fn should_restart(policy: RestartPolicy, status: &ExitStatus) -> bool {
+    match policy {
+        RestartPolicy::Always => true,
+        RestartPolicy::OnFailure => !status.success(),
+        RestartPolicy::Never => false,
+    }
+}
+
+/// # Restart Tracker
+///
+/// Spaces out the restarts of a component with a doubling delay and
+/// gives up once it restarted `max_restarts` times within the window
+#[derive(Debug, Default)]
+pub struct RestartTracker {
+    restarts: VecDeque<Instant>,
+    delay: Duration,
+    pub count: u32, // Restarts since the launcher started
+}
+
+impl RestartTracker {
+    /// Delay before the next restart, None once the component
+    /// is crash looping and should not be restarted again
+    pub fn next_delay(
+        &mut self,
+        now: Instant,
+        ran_for: Duration,
+        settings: &SupervisorSettings,
+    ) -> Option<Duration> {
+        let window = Duration::from_secs(settings.restart_window);
+        while let Some(restarted_at) = self.restarts.front() {
+            match now.duration_since(*restarted_at) > window {
+                true => self.restarts.pop_front(),
+                false => break,
+            };
+        }
+        if self.restarts.len() as u32 >= settings.max_restarts {
+            return None;
+        }
+
+        // a component that ran for a whole window starts over from the first delay
+        let initial_delay = Duration::from_millis(settings.restart_delay);
+        let max_delay = Duration::from_millis(settings.max_restart_delay);
+        self.delay = match self.delay.is_zero() || ran_for > window {
+            true => initial_delay,
+            false => (self.delay * 2).min(max_delay),
+        };
+
+        self.restarts.push_back(now);
+        self.count += 1;
+        Some(self.delay)
+    }
+
+    /// Forgets the recent restarts, for components started by hand
+    pub fn reset(&mut self) {
+        self.restarts.clear();
+        self.delay = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> SupervisorSettings {
+        SupervisorSettings {
+            restart_delay: 100,
+            max_restart_delay: 350,
+            max_restarts: 10,
+            restart_window: 60,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let settings = settings();
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        let delays: Vec<Option<Duration>> = (0..4)
+            .map(|_| tracker.next_delay(now, Duration::ZERO, &settings))
+            .collect();
+
+        assert_eq!(
+            delays,
+            [100, 200, 350, 350].map(|millis| Some(Duration::from_millis(millis)))
+        );
+        assert_eq!(tracker.count, 4);
+    }
+
+    #[test]
+    fn gives_up_after_max_restarts_within_the_window() {
+        let settings = SupervisorSettings {
+            max_restarts: 2,
+            ..settings()
+        };
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        assert!(tracker.next_delay(now, Duration::ZERO, &settings).is_some());
+        assert!(tracker.next_delay(now, Duration::ZERO, &settings).is_some());
+        assert_eq!(tracker.next_delay(now, Duration::ZERO, &settings), None);
+    }
+
+    #[test]
+    fn restarts_out_of_the_window_are_forgotten() {
+        let settings = SupervisorSettings {
+            max_restarts: 2,
+            ..settings()
+        };
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        tracker.next_delay(now, Duration::ZERO, &settings);
+        tracker.next_delay(now, Duration::ZERO, &settings);
+        let later = now + Duration::from_secs(61);
+
+        assert!(tracker.next_delay(later, Duration::ZERO, &settings).is_some());
+    }
+
+    #[test]
+    fn long_run_starts_over_from_the_first_delay() {
+        let settings = settings();
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        tracker.next_delay(now, Duration::ZERO, &settings);
+        tracker.next_delay(now, Duration::ZERO, &settings);
+        let delay = tracker.next_delay(now, Duration::from_secs(61), &settings);
+
+        assert_eq!(delay, Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn reset_starts_over_from_the_first_delay() {
+        let settings = SupervisorSettings {
+            max_restarts: 1,
+            ..settings()
+        };
+        let mut tracker = RestartTracker::default();
+        let now = Instant::now();
+
+        tracker.next_delay(now, Duration::ZERO, &settings);
+        tracker.reset();
+
+        assert_eq!(
+            tracker.next_delay(now, Duration::ZERO, &settings),
+            Some(Duration::from_millis(100))
+        );
+    }
+}
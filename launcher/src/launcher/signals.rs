@@ -0,0 +1,160 @@
+use std::{
+    io, mem,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::Command,
+    ptr,
+    sync::mpsc::Sender,
+    thread,
+};
+
+use anyhow::bail;
+use anyhow::Result;
+use tracing::{debug, error, info};
+
+use super::Event;
+use crate::errors::{LauncherError, LauncherErrorCodes};
+
+/// # Watch Signals
+///
+/// Turns SIGTERM and SIGINT into a shutdown event, the signals are
+/// blocked in the calling thread first so that threads spawned after
+/// it leave them to the signal thread. Must be called before any other
+/// thread is spawned
+pub fn watch_signals(events: Sender<Event>) -> Result<()> {
+    let signals = shutdown_signals();
+    if unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &signals, ptr::null_mut()) } != 0 {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SignalWatchError,
+            "unable to block the shutdown signals".to_string(),
+        ));
+    }
+
+    let spawn_result = thread::Builder::new()
+        .name("signals".to_string())
+        .spawn(move || loop {
+            let mut signal = 0;
+            if unsafe { libc::sigwait(&signals, &mut signal) } != 0 {
+                continue;
+            }
+            info!("received signal {}, shutting down", signal);
+            if events.send(Event::Shutdown).is_err() {
+                return;
+            }
+        });
+    if let Err(e) = spawn_result {
+        bail!(LauncherError::new(
+            LauncherErrorCodes::SignalWatchError,
+            format!("unable to start signal thread error: {}", e),
+        ));
+    }
+    Ok(())
+}
+
+/// Children inherit the blocked signals, they are unblocked again
+/// between fork and exec so components still exit on SIGTERM
+pub fn unblock_in_child(command: &mut Command) {
+    unsafe {
+        command.pre_exec(|| {
+            let signals = shutdown_signals();
+            libc::pthread_sigmask(libc::SIG_UNBLOCK, &signals, ptr::null_mut());
+            Ok(())
+        });
+    }
+}
+
+/// # Open Pidfd
+///
+/// A handle on the child that keeps pointing at it after it is reaped,
+/// so a signal sent through it never reaches a process that reused the
+/// pid. Must be opened before the child can be reaped, None on kernels
+/// without pidfds
+pub fn open_pidfd(pid: u32) -> Option<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        debug!(
+            "unable to open a pidfd for pid {}: {}",
+            pid,
+            io::Error::last_os_error()
+        );
+        return None;
+    }
+    Some(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
+
+/// Signals the component through its pidfd, by pid only when the
+/// kernel has no pidfds
+pub fn send_signal(name: &str, pid: u32, pidfd: Option<&OwnedFd>, signal: libc::c_int) {
+    let result = match pidfd {
+        Some(pidfd) => unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                pidfd.as_raw_fd(),
+                signal,
+                ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        },
+        None => unsafe { libc::kill(pid as libc::pid_t, signal) as libc::c_long },
+    };
+    if result == 0 {
+        return;
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        // it exited and its exit is still to be handled
+        Some(libc::ESRCH) => debug!("{} (pid {}) has already exited", name, pid),
+        _ => error!("Failed to signal {}: {}", name, e),
+    }
+}
+
+fn shutdown_signals() -> libc::sigset_t {
+    unsafe {
+        let mut signals: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut signals);
+        libc::sigaddset(&mut signals, libc::SIGTERM);
+        libc::sigaddset(&mut signals, libc::SIGINT);
+        signals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn signals_reach_the_child_through_its_pidfd() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let pidfd = open_pidfd(pid);
+
+        send_signal("sleep", pid, pidfd.as_ref(), libc::SIGKILL);
+        let status = child.wait().unwrap();
+
+        assert!(pidfd.is_some());
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[test]
+    fn reaped_children_are_not_signalled() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let pidfd = open_pidfd(child.id()).unwrap();
+        child.wait().unwrap();
+
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                pidfd.as_raw_fd(),
+                libc::SIGKILL,
+                ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+
+        assert_eq!(result, -1);
+        assert_eq!(io::Error::last_os_error().raw_os_error(), Some(libc::ESRCH));
+    }
+}
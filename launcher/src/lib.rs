@@ -0,0 +1,3 @@
+//! Parts of the launcher shared with `mecha-launcherctl`
+
+pub mod protocol;
@@ -0,0 +1,69 @@
+use std::{env, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Socket used when neither the settings nor the environment name one
+pub const SOCKET_NAME: &str = "mecha-launcher.sock";
+
+/// # Default Socket Path
+///
+/// `$MECHA_LAUNCHER_SOCKET` when set, else the socket in
+/// `$XDG_RUNTIME_DIR` (or `/tmp` without one)
+pub fn default_socket_path() -> PathBuf {
+    if let Ok(path) = env::var("MECHA_LAUNCHER_SOCKET") {
+        return PathBuf::from(path);
+    }
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").unwrap_or(String::from("/tmp"));
+    PathBuf::from(runtime_dir).join(SOCKET_NAME)
+}
+
+/// # Request
+///
+/// One request per line on the control socket, as JSON
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Start { component: String },
+    Stop { component: String },
+    Restart { component: String },
+    Logs { component: String, lines: usize },
+    // Sent by components with a watchdog to tell they are not hung
+    Ping { component: String },
+}
+
+/// # Response
+///
+/// One response line for every request
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Status {
+        components: Vec<ComponentStatus>,
+    },
+    Logs {
+        entries: Vec<LogEntry>,
+        #[serde(default)]
+        output: Option<PathBuf>, // Log with the stdout and stderr, when they are captured
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub state: String,
+    pub pid: Option<u32>,
+    pub uptime: Option<u64>, // Seconds
+    pub restarts: u32,
+    pub error: Option<String>, // Why a failed component failed
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: u64, // Seconds since the unix epoch
+    pub message: String,
+}
@@ -1,8 +1,9 @@
 use crate::errors::{LauncherError, LauncherErrorCodes};
 use anyhow::bail;
 use anyhow::Result;
+use mecha_launcher::protocol::default_socket_path;
 use serde::{Deserialize, Serialize};
-use std::{env, fs::File, path::PathBuf};
+use std::{collections::BTreeMap, env, fs::File, path::PathBuf};
 use tracing::{debug, info};
 
 /// # App Manager Settings
@@ -11,61 +12,215 @@ use tracing::{debug, info};
 /// this file lets you control the behavior of the app manager,
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct LauncherSettings {
-    pub components: Components,
+    #[serde(default)]
+    pub supervisor: SupervisorSettings,
+    #[serde(default)]
+    pub control: ControlSettings,
+    #[serde(default)]
+    pub logs: LogSettings,
+    #[serde(default)]
+    pub cgroups: CgroupSettings,
+    pub components: BTreeMap<String, Component>, // Keyed by the component name
 }
 
 impl Default for LauncherSettings {
     fn default() -> Self {
         Self {
-            components: Components::default(),
+            supervisor: SupervisorSettings::default(),
+            control: ControlSettings::default(),
+            logs: LogSettings::default(),
+            cgroups: CgroupSettings::default(),
+            components: BTreeMap::new(),
         }
     }
 }
 
+/// # Supervisor Settings
+///
+/// Part of the settings.yml to control how crashed
+/// components are restarted
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct SupervisorSettings {
+    pub restart_delay: u64,     // Milliseconds before the first restart, doubles on every restart
+    pub max_restart_delay: u64, // Milliseconds the restart delay is capped at
+    pub max_restarts: u32,      // Restarts within the window before a component is marked failed
+    pub restart_window: u64,    // Seconds
+    pub stop_timeout: u64,      // Milliseconds between SIGTERM and SIGKILL when stopping a component
+    pub ready_timeout: u64,     // Milliseconds a component gets to become ready before `after` dependents start anyway
+}
+
+impl Default for SupervisorSettings {
+    fn default() -> Self {
+        Self {
+            restart_delay: 500,
+            max_restart_delay: 30000,
+            max_restarts: 5,
+            restart_window: 60,
+            stop_timeout: 5000,
+            ready_timeout: 10000,
+        }
+    }
+}
+
+/// # Control Settings
+///
+/// Part of the settings.yml for the socket `mecha-launcherctl`
+/// talks to the launcher over
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct ControlSettings {
+    pub enabled: bool,
+    pub socket_path: String, // Empty uses $MECHA_LAUNCHER_SOCKET or $XDG_RUNTIME_DIR/mecha-launcher.sock
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: "".to_string(),
+        }
+    }
+}
+
+impl ControlSettings {
+    pub fn socket_path(&self) -> PathBuf {
+        match self.socket_path.is_empty() {
+            true => default_socket_path(),
+            false => PathBuf::from(&self.socket_path),
+        }
+    }
+}
 
+/// # Log Settings
+///
+/// Part of the settings.yml for where the stdout
+/// and stderr of the components go
 #[derive(Debug, Deserialize, Clone, Serialize)]
-pub struct Components {
-    pub status_bar: Component,
-    pub app_drawer: Component,
-    pub settings_panel: Component,
-    pub action_bar: Component,
-    pub app_dock: Component,
-    pub lock_screen: Component,
-    pub app_widget: Component,
+pub struct LogSettings {
+    pub capture: bool,     // Without it the components write to the launcher's stdout and stderr
+    pub directory: String, // Empty uses $XDG_STATE_HOME/mecha-launcher
+    pub max_size: u64,     // Bytes a log grows to before it is rotated, 0 never rotates
+    pub max_files: u32,    // Rotated logs kept per component
+    pub journald: bool,    // Also forwards every line to journald
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            capture: true,
+            directory: "".to_string(),
+            max_size: 1048576,
+            max_files: 3,
+            journald: false,
+        }
+    }
+}
+
+/// # Cgroup Settings
+///
+/// Part of the settings.yml for the cgroup v2 subtree delegated
+/// to the launcher, every component gets a cgroup of its own in it
+/// for the memory and cpu limits
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct CgroupSettings {
+    pub enabled: bool, // Needs a delegated subtree, e.g. `Delegate=yes` on the launcher's unit
+    pub root: String,  // Empty uses the cgroup the launcher is started in
 }
 
-impl Default for Components {
+impl Default for CgroupSettings {
     fn default() -> Self {
         Self {
-            status_bar: Component::default(),
-            app_drawer: Component::default(),
-            settings_panel: Component::default(),
-            action_bar: Component::default(),
-            app_dock: Component::default(),
-            lock_screen: Component::default(),
-            app_widget: Component::default(),
+            enabled: false,
+            root: "".to_string(),
         }
     }
 }
 
+/// # Component
+///
+/// A program the launcher starts and supervises, shell
+/// pieces as well as third party panels and daemons
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct Component {
     pub enabled: bool,
     pub bin_path: String,
-    pub settings_path: String
+    pub settings_path: String, // Passed as `--settings`, empty passes nothing
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub working_dir: String, // Empty keeps the working directory of the launcher
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default)]
+    pub after: Vec<String>, // Started once these are ready, or have failed
+    #[serde(default)]
+    pub requires: Vec<String>, // Only started once these are ready, fails once one of them failed
+    #[serde(default)]
+    pub readiness: Readiness,
+    #[serde(default)]
+    pub watchdog: u64, // Seconds between the pings the component sends, 0 turns the watchdog off
+    #[serde(default)]
+    pub limits: Limits,
 }
 
-
 impl Default for Component {
     fn default() -> Self {
         Self {
             enabled: false,
             bin_path: "".to_string(),
-            settings_path: "".to_string()
+            settings_path: "".to_string(),
+            args: vec![],
+            env: BTreeMap::new(),
+            working_dir: "".to_string(),
+            restart: RestartPolicy::default(),
+            after: vec![],
+            requires: vec![],
+            readiness: Readiness::default(),
+            watchdog: 0,
+            limits: Limits::default(),
         }
     }
 }
 
+/// # Limits
+///
+/// Resources and privileges a component is held to, the
+/// memory and cpu limits only apply with `cgroups` enabled
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Default)]
+pub struct Limits {
+    pub memory_max: u64,    // Bytes for memory.max, 0 is unlimited
+    pub cpu_weight: u32,    // 1 to 10000 for cpu.weight, 0 keeps the default of 100
+    pub nice: i32,          // -20 to 19, 0 keeps the niceness of the launcher
+    pub oom_score_adj: i32, // -1000 to 1000, 0 keeps the score of the launcher
+    pub no_new_privs: bool, // setuid binaries and file capabilities grant nothing
+    pub seccomp: bool,      // Denies system calls no shell component needs, implies no_new_privs
+}
+
+/// When a component is restarted after it exits
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    Always,
+    #[default]
+    OnFailure,
+    Never,
+}
+
+/// How a component tells it is ready for its dependents
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Default)]
+#[serde(tag = "kind")]
+pub enum Readiness {
+    /// Ready as soon as it is started
+    #[default]
+    Immediate,
+    /// Ready once the socket at the path appears
+    Socket { path: String },
+    /// Ready once the name is owned on the session bus
+    DbusName { name: String },
+    /// Ready once it sends `READY=1` to `$NOTIFY_SOCKET`, as with sd_notify
+    Notify,
+}
+
 /// # Reads Settings path from arg
 ///
 /// Reads the `-s` or `--settings` argument for the path
@@ -78,12 +233,11 @@ pub fn read_settings_path_from_args() -> Option<String> {
     None
 }
 
-/// # Reads Settings YML
+/// # Settings File Path
 ///
-/// Reads the `settings.yml` and parsers to LauncherSettings
-///
-/// **Important**: Ensure all fields are present in the yml due to strict parsing
-pub fn read_settings_yml() -> Result<LauncherSettings> {
+/// Path of the `settings.yml`, from the arguments or
+/// `MECHA_APP_MANAGER_SETTINGS_PATH`
+pub fn settings_file_path() -> PathBuf {
     let mut file_path = PathBuf::from(
         std::env::var("MECHA_APP_MANAGER_SETTINGS_PATH").unwrap_or(String::from("settings.yml")),
     ); // Get path of the library
@@ -93,6 +247,18 @@ pub fn read_settings_yml() -> Result<LauncherSettings> {
     if file_path_in_args.is_some() {
         file_path = PathBuf::from(file_path_in_args.unwrap());
     }
+    file_path
+}
+
+/// # Reads Settings YML
+///
+/// Reads the `settings.yml` and parsers to LauncherSettings
+///
+/// **Important**: Ensure all fields are present in the yml due to strict parsing,
+/// only the sections and the component fields marked `#[serde(default)]` may be
+/// left out so settings written before they were added still parse
+pub fn read_settings_yml() -> Result<LauncherSettings> {
+    let file_path = settings_file_path();
 
     info!(
         task = "read_settings",
@@ -126,3 +292,55 @@ pub fn read_settings_yml() -> Result<LauncherSettings> {
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_without_the_newer_sections_parse() {
+        let yml = "
+components:
+  shell:
+    enabled: true
+    bin_path: /usr/bin/mecha-shell
+    settings_path: ''
+    args: []
+    env: {}
+    working_dir: ''
+    restart: OnFailure
+    after: []
+    requires: []
+    readiness:
+      kind: Immediate
+";
+
+        let settings: LauncherSettings = serde_yaml::from_str(yml).unwrap();
+
+        let shell = &settings.components["shell"];
+        assert_eq!(shell.limits, Limits::default());
+        assert_eq!(shell.watchdog, 0);
+        assert!(!settings.cgroups.enabled);
+        assert_eq!(settings.supervisor.max_restarts, 5);
+    }
+
+    #[test]
+    fn components_without_the_newer_fields_parse() {
+        let yml = "
+components:
+  shell:
+    enabled: true
+    bin_path: /usr/bin/mecha-shell
+    settings_path: ''
+";
+
+        let settings: LauncherSettings = serde_yaml::from_str(yml).unwrap();
+
+        let shell = &settings.components["shell"];
+        assert!(shell.args.is_empty() && shell.env.is_empty());
+        assert_eq!(shell.working_dir, "");
+        assert_eq!(shell.restart, RestartPolicy::OnFailure);
+        assert!(shell.after.is_empty() && shell.requires.is_empty());
+        assert_eq!(shell.readiness, Readiness::Immediate);
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use dbus::{message::MatchRule, nonblock::Proxy, Path};
+use dbus_tokio::connection;
+use futures::StreamExt;
+use relm4::tokio::task::JoinHandle;
+use tracing::info;
+
+use super::{Authenticator, AuthenticatorEvent, EventCallback};
+use crate::{
+    errors::{LockScreenError, LockScreenErrorCodes},
+    settings::FingerprintAuthenticatorSettings,
+};
+
+const FPRINTD_BUS_NAME: &str = "net.reactivated.Fprint";
+const FPRINTD_MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const FPRINTD_MANAGER_INTERFACE: &str = "net.reactivated.Fprint.Manager";
+const FPRINTD_DEVICE_INTERFACE: &str = "net.reactivated.Fprint.Device";
+const DBUS_BUS_NAME: &str = "org.freedesktop.DBus";
+const DBUS_PATH: &str = "/org/freedesktop/DBus";
+const DBUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// # Fingerprint Authenticator
+///
+/// Verifies the user with the default fprintd device over the system bus,
+/// the device is released by fprintd as soon as the connection is dropped
+pub struct FingerprintAuthenticator {
+    settings: FingerprintAuthenticatorSettings,
+    task: Option<JoinHandle<()>>,
+}
+
+impl FingerprintAuthenticator {
+    pub fn new(settings: FingerprintAuthenticatorSettings) -> Self {
+        Self {
+            settings,
+            task: None,
+        }
+    }
+}
+
+impl Authenticator for FingerprintAuthenticator {
+    fn name(&self) -> &'static str {
+        "fingerprint"
+    }
+
+    fn start(&mut self, username: Option<String>, on_event: EventCallback) {
+        self.stop();
+        let settings = self.settings.clone();
+        self.task = Some(relm4::spawn(async move {
+            if let Err(e) = verify(settings, username, on_event.clone()).await {
+                let message = match e.downcast_ref::<LockScreenError>().map(|e| e.code) {
+                    Some(LockScreenErrorCodes::FingerprintAttemptsExceeded) => {
+                        "Too many fingerprint attempts, use your PIN"
+                    }
+                    _ => "Fingerprint is unavailable",
+                };
+                on_event(AuthenticatorEvent::Unavailable(message.to_string()));
+            }
+        }));
+    }
+
+    fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for FingerprintAuthenticator {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Aborts the connection task once verification ends, or is aborted itself
+struct ConnectionGuard(JoinHandle<()>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn verify(
+    settings: FingerprintAuthenticatorSettings,
+    username: Option<String>,
+    on_event: EventCallback,
+) -> Result<()> {
+    let (resource, conn) = match connection::new_system_sync() {
+        Ok(r) => r,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintConnectError,
+                format!("unable to connect to system bus error: {}", e),
+            ));
+        }
+    };
+    let _connection_guard = ConnectionGuard(relm4::spawn(async move {
+        let _ = resource.await;
+    }));
+
+    let manager = Proxy::new(
+        FPRINTD_BUS_NAME,
+        FPRINTD_MANAGER_PATH,
+        DBUS_TIMEOUT,
+        conn.clone(),
+    );
+    let device_path: Path<'static> = match manager
+        .method_call(FPRINTD_MANAGER_INTERFACE, "GetDefaultDevice", ())
+        .await
+    {
+        Ok((device_path,)) => device_path,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintDeviceError,
+                format!("unable to find a fingerprint device error: {}", e),
+            ));
+        }
+    };
+    info!(task = "fingerprint", "using fingerprint device {}", device_path);
+
+    let device = Proxy::new(
+        FPRINTD_BUS_NAME,
+        device_path.clone(),
+        DBUS_TIMEOUT,
+        conn.clone(),
+    );
+    // an empty username claims the device for the user running the lock screen
+    let claim_result: Result<(), dbus::Error> = device
+        .method_call(
+            FPRINTD_DEVICE_INTERFACE,
+            "Claim",
+            (username.unwrap_or_default(),),
+        )
+        .await;
+    if let Err(e) = claim_result {
+        bail!(LockScreenError::new(
+            LockScreenErrorCodes::FingerprintDeviceError,
+            format!("unable to claim fingerprint device error: {}", e),
+        ));
+    }
+
+    // signals are sent from the unique name of fprintd, matching on it keeps
+    // any other client from reporting a verification as done
+    let bus = Proxy::new(DBUS_BUS_NAME, DBUS_PATH, DBUS_TIMEOUT, conn.clone());
+    let fprintd_name: String = match bus
+        .method_call(DBUS_BUS_NAME, "GetNameOwner", (FPRINTD_BUS_NAME,))
+        .await
+    {
+        Ok((fprintd_name,)) => fprintd_name,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintVerifyError,
+                format!("unable to find the fprintd connection error: {}", e),
+            ));
+        }
+    };
+    let verify_status_rule = MatchRule::new_signal(FPRINTD_DEVICE_INTERFACE, "VerifyStatus")
+        .with_sender(fprintd_name)
+        .with_path(device_path.clone());
+    let (_verify_status_match, mut verify_statuses) = match conn.add_match(verify_status_rule).await
+    {
+        Ok(msg_match) => msg_match.stream::<(String, bool)>(),
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintVerifyError,
+                format!("unable to listen for verify status error: {}", e),
+            ));
+        }
+    };
+
+    let mut failed_attempts = 0;
+    loop {
+        let verify_start_result: Result<(), dbus::Error> = device
+            .method_call(
+                FPRINTD_DEVICE_INTERFACE,
+                "VerifyStart",
+                (settings.finger.as_str(),),
+            )
+            .await;
+        if let Err(e) = verify_start_result {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintVerifyError,
+                format!("unable to start fingerprint verification error: {}", e),
+            ));
+        }
+        on_event(AuthenticatorEvent::Prompt(
+            "Touch the fingerprint sensor".to_string(),
+        ));
+
+        // a verification runs until fprintd reports it done
+        loop {
+            let (result, is_done) = match verify_statuses.next().await {
+                Some((_, status)) => status,
+                None => {
+                    bail!(LockScreenError::new(
+                        LockScreenErrorCodes::FingerprintVerifyError,
+                        "lost connection to fprintd".to_string(),
+                    ));
+                }
+            };
+            info!(task = "fingerprint", "verify status {} done {}", result, is_done);
+
+            match result.as_str() {
+                "verify-match" => {
+                    on_event(AuthenticatorEvent::Succeeded);
+                    return Ok(());
+                }
+                "verify-no-match" => {
+                    failed_attempts += 1;
+                    on_event(AuthenticatorEvent::Failed(
+                        "Fingerprint not recognized".to_string(),
+                    ));
+                }
+                "verify-retry-scan"
+                | "verify-swipe-too-short"
+                | "verify-finger-not-centered"
+                | "verify-remove-and-retry" => {
+                    on_event(AuthenticatorEvent::Prompt(
+                        "Lift your finger and try again".to_string(),
+                    ));
+                }
+                _ => {
+                    bail!(LockScreenError::new(
+                        LockScreenErrorCodes::FingerprintVerifyError,
+                        format!("fingerprint verification failed - {}", result),
+                    ));
+                }
+            }
+
+            if is_done {
+                break;
+            }
+        }
+
+        let _: Result<(), dbus::Error> = device
+            .method_call(FPRINTD_DEVICE_INTERFACE, "VerifyStop", ())
+            .await;
+
+        if settings.max_attempts > 0 && failed_attempts >= settings.max_attempts {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::FingerprintAttemptsExceeded,
+                format!("fingerprint attempts exceeded {}", failed_attempts),
+            ));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use crate::settings::AuthenticatorsSettings;
+
+pub mod fingerprint;
+
+use fingerprint::FingerprintAuthenticator;
+
+/// # Authenticator Event
+///
+/// Progress reported by an authenticator while it runs
+#[derive(Debug, Clone)]
+pub enum AuthenticatorEvent {
+    Prompt(String),      // Waiting on the user, e.g. to touch the sensor
+    Failed(String),      // An attempt did not match
+    Unavailable(String), // Gave up, the other methods remain
+    Succeeded,
+}
+
+pub type EventCallback = Arc<dyn Fn(AuthenticatorEvent) + Send + Sync>;
+
+/// # Authenticator
+///
+/// An authentication method that runs in the background next to
+/// the auth pages, the first method to succeed unlocks the screen
+pub trait Authenticator {
+    fn name(&self) -> &'static str;
+
+    /// Starts verifying the user, reporting progress through
+    /// `on_event` until it succeeds, gives up or is stopped
+    fn start(&mut self, username: Option<String>, on_event: EventCallback);
+
+    fn stop(&mut self);
+}
+
+/// Authenticators enabled in the settings
+pub fn from_settings(settings: &AuthenticatorsSettings) -> Vec<Box<dyn Authenticator>> {
+    let mut authenticators: Vec<Box<dyn Authenticator>> = vec![];
+    if settings.fingerprint.enabled {
+        authenticators.push(Box::new(FingerprintAuthenticator::new(
+            settings.fingerprint.clone(),
+        )));
+    }
+    authenticators
+}
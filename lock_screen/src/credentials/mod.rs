@@ -0,0 +1,241 @@
+use crate::errors::{LockScreenError, LockScreenErrorCodes};
+use anyhow::bail;
+use anyhow::Result;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+};
+use tracing::info;
+
+/// # Credentials
+///
+/// Struct representing the credentials.yml store, secrets are kept
+/// as argon2 PHC strings and never in plain text
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct Credentials {
+    pub pin: Option<String>,
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub failed_pin_changes: u32, // Wrong current PINs in a row while changing the PIN
+    #[serde(default)]
+    pub unlock_attempts: UnlockAttempts,
+}
+
+/// Wrong PINs and patterns entered in a row on the lock screen
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, Default, PartialEq, Eq)]
+pub struct UnlockAttempts {
+    pub failed_pins: u32,
+    pub failed_patterns: u32,
+    pub locked_until: u64, // Unix seconds PIN and pattern wait for once they ran out without a password
+}
+
+/// # Hash Secret
+///
+/// Hashes a PIN or pattern with a fresh salt
+pub fn hash_secret(secret: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    match Argon2::default().hash_password(secret.as_bytes(), &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::CredentialsHashError,
+                format!("unable to hash secret error: {}", e),
+            ));
+        }
+    }
+}
+
+/// # Verify Secret
+///
+/// Checks a PIN or pattern against its stored hash
+pub fn verify_secret(secret: &str, hash: &str) -> bool {
+    let parsed_hash = match PasswordHash::new(hash) {
+        Ok(parsed_hash) => parsed_hash,
+        Err(e) => {
+            LockScreenError::new(
+                LockScreenErrorCodes::CredentialsParseError,
+                format!("stored hash is invalid - {}", e),
+            );
+            return false;
+        }
+    };
+    Argon2::default()
+        .verify_password(secret.as_bytes(), &parsed_hash)
+        .is_ok()
+}
+
+/// # Reads Credentials YML
+///
+/// Reads the credentials file and parses to Credentials
+pub fn read_credentials_yml(path: &str) -> Result<Credentials> {
+    let file_path = PathBuf::from(path);
+
+    info!(
+        task = "read_credentials",
+        "credentials file location - {:?}", file_path
+    );
+
+    // open file
+    let credentials_file_handle = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::CredentialsReadError,
+                format!("cannot read the credentials in the path - {}", e),
+            ));
+        }
+    };
+
+    // read and parse
+    let credentials: Credentials = match serde_yaml::from_reader(credentials_file_handle) {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::CredentialsParseError,
+                format!("error parsing the credentials - {}", e),
+            ));
+        }
+    };
+
+    Ok(credentials)
+}
+
+/// # Writes Credentials YML
+///
+/// Serializes Credentials back to the credentials file, readable by
+/// the owner only. The file is written next to it first and renamed
+/// over it, so a crash never leaves the secrets half written
+pub fn write_credentials_yml(path: &str, credentials: &Credentials) -> Result<()> {
+    let content = match serde_yaml::to_string(credentials) {
+        Ok(content) => content,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::CredentialsWriteError,
+                format!("error serializing the credentials - {}", e),
+            ));
+        }
+    };
+
+    let temp_path = format!("{}.{}.tmp", path, process::id());
+    let write_result = File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    match write_result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::CredentialsWriteError,
+                format!("cannot write the credentials in the path - {}", e),
+            ));
+        }
+    }
+}
+
+/// # Save PIN
+///
+/// Hashes the PIN into the credentials file, keeping the other
+/// credentials already stored there
+pub fn save_pin(path: &str, pin: &str) -> Result<()> {
+    let mut credentials = match Path::new(path).exists() {
+        true => read_credentials_yml(path)?,
+        false => Credentials::default(),
+    };
+    credentials.pin = Some(hash_secret(pin)?);
+    write_credentials_yml(path, &credentials)
+}
+
+/// # Save Pattern
+///
+/// Hashes the pattern, in its stored form of dot indexes, into
+/// the credentials file, keeping the other credentials stored there
+pub fn save_pattern(path: &str, pattern: &str) -> Result<()> {
+    let mut credentials = match Path::new(path).exists() {
+        true => read_credentials_yml(path)?,
+        false => Credentials::default(),
+    };
+    credentials.pattern = Some(hash_secret(pattern)?);
+    write_credentials_yml(path, &credentials)
+}
+
+/// # Save Failed PIN Changes
+///
+/// Stores how many wrong current PINs were entered in a row while
+/// changing the PIN, so starting the change over gives no fresh guesses
+pub fn save_failed_pin_changes(path: &str, failed: u32) -> Result<()> {
+    // without a file there is no pin to guess, and nothing to reset
+    if failed == 0 && !Path::new(path).exists() {
+        return Ok(());
+    }
+    let mut credentials = read_credentials_yml(path)?;
+    if credentials.failed_pin_changes == failed {
+        return Ok(());
+    }
+    credentials.failed_pin_changes = failed;
+    write_credentials_yml(path, &credentials)
+}
+
+/// # Save Unlock Attempts
+///
+/// Stores the wrong PINs and patterns entered in a row, so restarting
+/// the lock screen gives no fresh guesses
+pub fn save_unlock_attempts(path: &str, attempts: &UnlockAttempts) -> Result<()> {
+    // without a file there is nothing to guess, and nothing to reset
+    if *attempts == UnlockAttempts::default() && !Path::new(path).exists() {
+        return Ok(());
+    }
+    let mut credentials = read_credentials_yml(path)?;
+    if credentials.unlock_attempts == *attempts {
+        return Ok(());
+    }
+    credentials.unlock_attempts = *attempts;
+    write_credentials_yml(path, &credentials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, os::unix::fs::PermissionsExt};
+
+    #[test]
+    fn credentials_are_replaced_privately() {
+        let path = env::temp_dir().join(format!("credentials-{}.yml", process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "pin: ~\npattern: ~\n").unwrap();
+
+        save_pin(path, "1590").unwrap();
+        save_failed_pin_changes(path, 2).unwrap();
+        let attempts = UnlockAttempts {
+            failed_pins: 3,
+            failed_patterns: 1,
+            locked_until: 60,
+        };
+        save_unlock_attempts(path, &attempts).unwrap();
+        let credentials = read_credentials_yml(path).unwrap();
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        let is_temp_file_left = Path::new(&format!("{}.{}.tmp", path, process::id())).exists();
+        let _ = fs::remove_file(path);
+
+        assert!(verify_secret("1590", &credentials.pin.unwrap()));
+        assert_eq!(credentials.failed_pin_changes, 2);
+        assert_eq!(credentials.unlock_attempts, attempts);
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!is_temp_file_left);
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use dbus::{
+    channel::{Channel, MatchingReceiver, Sender},
+    message::MatchRule,
+    nonblock::SyncConnection,
+    strings::{Interface, Member},
+    Message as DbusMessage, Path,
+};
+use dbus_crossroads::{Crossroads, IfaceBuilder};
+use dbus_tokio::connection::{self, IOResource};
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::{
+    errors::{LockScreenError, LockScreenErrorCodes},
+    settings::DbusSettings,
+    Message,
+};
+
+pub const BUS_NAME: &str = "org.mecha.LockScreen";
+pub const OBJECT_PATH: &str = "/org/mecha/LockScreen";
+pub const INTERFACE_NAME: &str = "org.mecha.LockScreen";
+
+/// State the interface methods get to
+struct Service {
+    sender: relm4::Sender<Message>,
+    is_locked: watch::Receiver<bool>,
+}
+
+/// # Connect
+///
+/// Connects to the configured bus, the resource drives the
+/// connection once it is handed to `serve`
+pub fn connect(
+    settings: &DbusSettings,
+) -> Result<(IOResource<SyncConnection>, Arc<SyncConnection>)> {
+    let connection_result = match settings.system_bus {
+        true => connection::new_system_sync(),
+        false => connection::new_session_sync(),
+    };
+    match connection_result {
+        Ok(r) => Ok(r),
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::DbusConnectError,
+                format!("unable to connect to dbus error: {}", e),
+            ));
+        }
+    }
+}
+
+/// # Serve
+///
+/// Claims `org.mecha.LockScreen` on the connection and forwards
+/// the method calls to the lock screen as messages, runs until the
+/// connection to the bus is lost.
+///
+/// `is_locked` is the lock state of the lock screen, `Locked` is
+/// signalled once the interface is up, later changes are signalled
+/// by the lock screen with `signal_lock_state`
+pub async fn serve(
+    resource: IOResource<SyncConnection>,
+    conn: Arc<SyncConnection>,
+    sender: relm4::Sender<Message>,
+    is_locked: watch::Receiver<bool>,
+) -> Result<()> {
+    // the resource drives the connection, it only resolves once the connection is lost
+    let connection_lost = relm4::spawn(async move { resource.await.to_string() });
+
+    if let Err(e) = conn.request_name(BUS_NAME, false, true, false).await {
+        bail!(LockScreenError::new(
+            LockScreenErrorCodes::DbusRequestNameError,
+            format!("unable to acquire dbus name {} error: {}", BUS_NAME, e),
+        ));
+    }
+
+    let mut cr = Crossroads::new();
+    let iface_token = cr.register(INTERFACE_NAME, |b: &mut IfaceBuilder<Service>| {
+        b.signal::<(), _>("Locked", ());
+        b.signal::<(), _>("Unlocked", ());
+        b.method("Lock", (), (), |_, service, _: ()| {
+            info!(task = "dbus", "lock requested");
+            let _ = service.sender.send(Message::Lock);
+            Ok(())
+        });
+        b.method("IsLocked", (), ("locked",), |_, service, _: ()| {
+            Ok((*service.is_locked.borrow(),))
+        });
+        b.method(
+            "SetLostDeviceMessage",
+            ("message",),
+            (),
+            |_, service, (message,): (String,)| {
+                info!(task = "dbus", "lost device message set to {}", message);
+                let _ = service
+                    .sender
+                    .send(Message::LostDeviceMessageChanged(Some(message)));
+                Ok(())
+            },
+        );
+        b.method("ClearLostDeviceMessage", (), (), |_, service, _: ()| {
+            info!(task = "dbus", "lost device message cleared");
+            let _ = service.sender.send(Message::LostDeviceMessageChanged(None));
+            Ok(())
+        });
+        b.method("ChangePin", (), (), |_, service, _: ()| {
+            info!(task = "dbus", "pin change requested");
+            let _ = service.sender.send(Message::ChangePinRequested);
+            Ok(())
+        });
+    });
+    let is_locked_now = *is_locked.borrow();
+    let service = Service { sender, is_locked };
+    cr.insert(OBJECT_PATH, &[iface_token], service);
+
+    conn.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            let _ = cr.handle_message(msg, conn);
+            true
+        }),
+    );
+
+    signal_lock_state(&conn, is_locked_now);
+
+    let error = connection_lost.await.unwrap_or_default();
+    bail!(LockScreenError::new(
+        LockScreenErrorCodes::DbusConnectionLost,
+        format!("lost connection to dbus error: {}", error),
+    ));
+}
+
+/// Signals `Locked` or `Unlocked`, sent by the lock screen
+/// whenever its lock state changes
+pub fn signal_lock_state(conn: &SyncConnection, is_locked: bool) {
+    let member = match is_locked {
+        true => Member::from("Locked"),
+        false => Member::from("Unlocked"),
+    };
+    let path = Path::from(OBJECT_PATH);
+    let interface = Interface::from(INTERFACE_NAME);
+    let _ = conn.send(DbusMessage::signal(&path, &interface, &member));
+}
+
+/// Blocks until the queued messages are written, a signal
+/// sent right before quitting is lost otherwise
+pub fn flush(conn: &SyncConnection) {
+    AsRef::<Channel>::as_ref(conn).flush();
+}
@@ -16,11 +16,35 @@ pub enum LockScreenErrorCodes {
     FindLoginManagerUrlError,
     LoginManagerStreamConnectError,
     StreamWriteUsernameError,
-    StreamReadEnterPasswordError,
     StreamWritePasswordError,
-    StreamReadCaptchaError,
-    StreamWriteCaptchaError,
+    StreamWriteAuthResponseError,
     StreamReadAuthResponseError,
+    LoginTimeoutError,
+    InitNetworkManagerClient,
+    InitBatteryManagerClient,
+    GetWifiStatusError,
+    GetBatteryStatusError,
+    PreferencesReadError,
+    PreferencesParseError,
+    PreferencesWriteError,
+    UsersReadError,
+    SessionsReadError,
+    StartSessionError,
+    NoSessionError,
+    DbusConnectError,
+    DbusRequestNameError,
+    DbusConnectionLost,
+    FingerprintConnectError,
+    FingerprintDeviceError,
+    FingerprintVerifyError,
+    FingerprintAttemptsExceeded,
+    CredentialsReadError,
+    CredentialsParseError,
+    CredentialsWriteError,
+    CredentialsHashError,
+    InvalidPattern,
+    InvalidClockFormat,
+    WrongCurrentCredentials,
 }
 
 impl fmt::Display for LockScreenErrorCodes {
@@ -38,21 +62,49 @@ impl fmt::Display for LockScreenErrorCodes {
             LockScreenErrorCodes::StreamWriteUsernameError => {
                 write!(f, "StreamWriteUsernameError")
             }
-            LockScreenErrorCodes::StreamReadEnterPasswordError => {
-                write!(f, "StreamReadEnterPasswordError")
-            }
             LockScreenErrorCodes::StreamWritePasswordError => {
                 write!(f, "StreamWritePasswordError")
             }
-            LockScreenErrorCodes::StreamReadCaptchaError => {
-                write!(f, "StreamReadCaptchaError")
-            }
-            LockScreenErrorCodes::StreamWriteCaptchaError => {
-                write!(f, "StreamWriteCaptchaError")
+            LockScreenErrorCodes::StreamWriteAuthResponseError => {
+                write!(f, "StreamWriteAuthResponseError")
             }
             LockScreenErrorCodes::StreamReadAuthResponseError => {
                 write!(f, "StreamReadAuthResponseError")
             }
+            LockScreenErrorCodes::LoginTimeoutError => write!(f, "LoginTimeoutError"),
+            LockScreenErrorCodes::InitNetworkManagerClient => {
+                write!(f, "InitNetworkManagerClient")
+            }
+            LockScreenErrorCodes::InitBatteryManagerClient => {
+                write!(f, "InitBatteryManagerClient")
+            }
+            LockScreenErrorCodes::GetWifiStatusError => write!(f, "GetWifiStatusError"),
+            LockScreenErrorCodes::GetBatteryStatusError => write!(f, "GetBatteryStatusError"),
+            LockScreenErrorCodes::PreferencesReadError => write!(f, "PreferencesReadError"),
+            LockScreenErrorCodes::PreferencesParseError => write!(f, "PreferencesParseError"),
+            LockScreenErrorCodes::PreferencesWriteError => write!(f, "PreferencesWriteError"),
+            LockScreenErrorCodes::UsersReadError => write!(f, "UsersReadError"),
+            LockScreenErrorCodes::SessionsReadError => write!(f, "SessionsReadError"),
+            LockScreenErrorCodes::StartSessionError => write!(f, "StartSessionError"),
+            LockScreenErrorCodes::NoSessionError => write!(f, "NoSessionError"),
+            LockScreenErrorCodes::DbusConnectError => write!(f, "DbusConnectError"),
+            LockScreenErrorCodes::DbusRequestNameError => write!(f, "DbusRequestNameError"),
+            LockScreenErrorCodes::DbusConnectionLost => write!(f, "DbusConnectionLost"),
+            LockScreenErrorCodes::FingerprintConnectError => write!(f, "FingerprintConnectError"),
+            LockScreenErrorCodes::FingerprintDeviceError => write!(f, "FingerprintDeviceError"),
+            LockScreenErrorCodes::FingerprintVerifyError => write!(f, "FingerprintVerifyError"),
+            LockScreenErrorCodes::FingerprintAttemptsExceeded => {
+                write!(f, "FingerprintAttemptsExceeded")
+            }
+            LockScreenErrorCodes::CredentialsReadError => write!(f, "CredentialsReadError"),
+            LockScreenErrorCodes::CredentialsParseError => write!(f, "CredentialsParseError"),
+            LockScreenErrorCodes::CredentialsWriteError => write!(f, "CredentialsWriteError"),
+            LockScreenErrorCodes::CredentialsHashError => write!(f, "CredentialsHashError"),
+            LockScreenErrorCodes::InvalidPattern => write!(f, "InvalidPattern"),
+            LockScreenErrorCodes::InvalidClockFormat => write!(f, "InvalidClockFormat"),
+            LockScreenErrorCodes::WrongCurrentCredentials => {
+                write!(f, "WrongCurrentCredentials")
+            }
         }
     }
 }
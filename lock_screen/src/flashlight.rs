@@ -0,0 +1,23 @@
+use std::{fs, path::Path};
+
+use tracing::error;
+
+/// # Set Flashlight
+///
+/// Switches a sysfs torch led fully on (`max_brightness`) or off
+pub fn set_flashlight(device_path: &str, is_on: bool) {
+    let device_path = Path::new(device_path);
+    let brightness = match is_on {
+        true => match fs::read_to_string(device_path.join("max_brightness")) {
+            Ok(v) => v.trim().to_string(),
+            Err(e) => {
+                error!("unable to read flashlight max brightness error: {}", e);
+                return;
+            }
+        },
+        false => "0".to_string(),
+    };
+    if let Err(e) = fs::write(device_path.join("brightness"), brightness) {
+        error!("unable to set flashlight brightness error: {}", e);
+    }
+}
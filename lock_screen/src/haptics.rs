@@ -0,0 +1,18 @@
+use std::{fs, path::Path};
+
+use tracing::error;
+
+/// # Vibrate
+///
+/// Runs a short vibration on a sysfs vibrator using the led
+/// `transient` trigger (`duration` in ms, then `activate`)
+pub fn vibrate(device_path: &str, duration: u32) {
+    let device_path = Path::new(device_path);
+    if let Err(e) = fs::write(device_path.join("duration"), duration.to_string()) {
+        error!("unable to set vibration duration error: {}", e);
+        return;
+    }
+    if let Err(e) = fs::write(device_path.join("activate"), "1") {
+        error!("unable to activate vibration error: {}", e);
+    }
+}
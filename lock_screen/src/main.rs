@@ -1,29 +1,61 @@
-use std::fmt;
+use std::{cell::Cell, fmt, io, path::Path, sync::Arc};
 
-use gtk::{glib::clone, prelude::GtkWindowExt};
+use anyhow::bail;
+use anyhow::Result;
+
+use gtk::{
+    glib::{self, clone},
+    prelude::{GtkWindowExt, WidgetExt},
+};
 use relm4::gtk::LayoutManager;
 use relm4::{gtk, ComponentParts, ComponentSender, RelmApp, SimpleComponent};
 use relm4::{Component, ComponentController, Controller};
-
+use dbus::nonblock::SyncConnection;
+use tokio::sync::watch;
+
+mod authenticators;
+mod credentials;
+mod dbus_service;
+mod flashlight;
+mod haptics;
+#[cfg(test)]
+mod mock_greetd;
 mod pages;
+mod preferences;
+mod sessions;
 mod settings;
 mod theme;
+mod users;
 mod widgets;
 use pages::{
+    attempts::{Attempts, Lockout, Settings as AttemptsSettings},
     home::{HomePage, Message as HomePageMessage, Settings as HomePageSettings},
     password_authentication::{
         Message as PasswordAuthenticationMessage, PasswordAuthentication,
         Settings as PasswordAuthenticationSettings,
     },
+    pattern_authentication::{
+        pattern_from_string, pattern_to_string, Message as PatternAuthenticationMessage,
+        PatternAuthentication, Settings as PatternAuthenticationSettings,
+    },
     pin_authentication::{
         Message as PinAuthenticationMessage, PinAuthentication,
         Settings as PinAuthenticationSettings,
     },
+    pin_enrollment::{
+        Message as PinEnrollmentMessage, PinEnrollment, Settings as PinEnrollmentSettings,
+    },
 };
 use tracing::{error, info};
 pub mod errors;
 
-use crate::settings::LockScreenSettings;
+use crate::authenticators::{Authenticator, AuthenticatorEvent};
+use crate::credentials::Credentials;
+use crate::errors::{LockScreenError, LockScreenErrorCodes};
+use crate::preferences::AuthPreferences;
+use crate::settings::{
+    AuthMethod, AuthSettings, LockScreenSettings, TransitionType, TransitionsSettings,
+};
 use crate::theme::LockScreenTheme;
 
 /// # LockScreen State
@@ -32,10 +64,22 @@ use crate::theme::LockScreenTheme;
 struct LockScreen {
     settings: LockScreenSettings,
     custom_theme: LockScreenTheme,
+    preferences: AuthPreferences,
     current_screen: Screens,
+    transition: TransitionType,
+    is_unlocking: bool,
+    is_unlocked: bool,     // Hidden until `Lock` is called over dbus
+    is_lock_pending: bool, // `Lock` came while fading out, locks again once faded
+    lock_state: watch::Sender<bool>,
+    dbus_connection: Option<Arc<SyncConnection>>,
     home_page: Controller<HomePage>,
     pin_authentication_page: Controller<PinAuthentication>,
     password_authentication_page: Controller<PasswordAuthentication>,
+    pattern_authentication_page: Controller<PatternAuthentication>,
+    pin_enrollment_page: Controller<PinEnrollment>,
+    quit_after_pin_change: bool,
+    authenticators: Vec<Box<dyn Authenticator>>,
+    attempts: Attempts,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +87,30 @@ pub enum Screens {
     LockScreen,
     PasswordScreen,
     PinScreen,
+    PatternScreen,
+    PinEnrollmentScreen,
+}
+
+impl Screens {
+    /// Authentication method the screen belongs to, if any
+    fn auth_method(&self) -> Option<AuthMethod> {
+        match self {
+            Screens::LockScreen | Screens::PinEnrollmentScreen => None,
+            Screens::PasswordScreen => Some(AuthMethod::Password),
+            Screens::PinScreen => Some(AuthMethod::Pin),
+            Screens::PatternScreen => Some(AuthMethod::Pattern),
+        }
+    }
+}
+
+impl From<AuthMethod> for Screens {
+    fn from(method: AuthMethod) -> Self {
+        match method {
+            AuthMethod::Pin => Screens::PinScreen,
+            AuthMethod::Password => Screens::PasswordScreen,
+            AuthMethod::Pattern => Screens::PatternScreen,
+        }
+    }
 }
 
 impl fmt::Display for Screens {
@@ -51,6 +119,8 @@ impl fmt::Display for Screens {
             Screens::LockScreen => write!(f, "lock_screen"),
             Screens::PasswordScreen => write!(f, "password_screen"),
             Screens::PinScreen => write!(f, "pin_screen"),
+            Screens::PatternScreen => write!(f, "pattern_screen"),
+            Screens::PinEnrollmentScreen => write!(f, "pin_enrollment_screen"),
         }
     }
 }
@@ -62,10 +132,44 @@ impl fmt::Display for Screens {
 #[derive(Debug, Clone)]
 pub enum Message {
     ChangeScreen(Screens),
+    AuthRequested,
+    AttemptsExceeded,
+    LoggedIn(String),
+    LostDeviceMessageChanged(Option<String>),
+    Lock,
+    Unlock,
+    UnlockFaded,
+    AuthenticatorEvent(AuthenticatorEvent),
+    ChangePinRequested,
+    PinChangeFinished,
     Dummy,
 }
 
+/// # Args
+///
+/// Command line flags of the lock screen
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Args {
+    pub change_pin: bool,  // Only run the change pin flow, then quit
+    pub set_pattern: bool, // Store the pattern read from stdin, then quit
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut parsed = Args::default();
+        for arg in args {
+            match arg.as_str() {
+                "--change-pin" => parsed.change_pin = true,
+                "--set-pattern" => parsed.set_pattern = true,
+                _ => info!("ignoring unknown argument {}", arg),
+            }
+        }
+        parsed
+    }
+}
+
 struct AppWidgets {
+    window: gtk::Window,
     screens_stack: gtk::Stack,
 }
 
@@ -127,7 +231,7 @@ impl SimpleComponent for LockScreen {
     /// The type of the messages that this component can send.
     type Output = ();
     /// The type of data with which this component will be initialized.
-    type Init = ();
+    type Init = Args;
     /// The root GTK widget that this component will create.
     type Root = gtk::Window;
     /// A data structure that contains the widgets that you will need to update.
@@ -160,7 +264,7 @@ impl SimpleComponent for LockScreen {
 
     /// Initialize the UI and model.
     fn init(
-        _: Self::Init,
+        args: Self::Init,
         window: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> relm4::ComponentParts<Self> {
@@ -181,28 +285,62 @@ impl SimpleComponent for LockScreen {
 
         let modules = settings.modules.clone();
         let layout = settings.layout.clone();
+        let auth = settings.auth.clone();
+
+        let preferences = match auth.remember_last_method {
+            true => preferences::read_preferences_yml(&auth.preferences_path).unwrap_or_default(),
+            false => AuthPreferences::default(),
+        };
 
         //Stack used to render different screens
         //At a time one screen will be rendered
-        let screens_stack = gtk::Stack::builder().build();
+        let screens_stack = gtk::Stack::builder()
+            .css_classes(["screens-stack"])
+            .transition_duration(settings.transitions.duration)
+            .build();
 
         let home_page = HomePage::builder()
             .launch(HomePageSettings {
                 lock_icon: modules.lock.icon.default.to_owned(),
                 unlock_icon: modules.unlock.icon.default.to_owned(),
                 password_icon: modules.home_password.icon.default.to_owned(),
+                pattern_icon: modules.home_pattern.icon.default.to_owned(),
+                pin_enabled: auth.pin_enabled,
+                password_enabled: auth.password_enabled,
+                pattern_enabled: auth.pattern_enabled,
+                swipe_navigation: settings.transitions.swipe_navigation,
+                clock: modules.clock.clone(),
+                wifi: modules.wifi.clone(),
+                battery: modules.battery.clone(),
+                owner_message: modules.owner_message.clone(),
+                flashlight: modules.flashlight.clone(),
+                emergency: modules.emergency.clone(),
+                background: custom_theme.background.default.clone(),
             })
             .forward(
                 sender.input_sender(),
                 clone!(@strong modules => move|msg| match msg {
-                    HomePageMessage::ChangeScreen(screen) => Message::ChangeScreen(screen)
+                    HomePageMessage::ChangeScreen(screen) => Message::ChangeScreen(screen),
+                    HomePageMessage::AuthRequested => Message::AuthRequested,
+                    _ => Message::Dummy
                 }),
             );
 
+        // running out of pins or patterns leaves the password, or a delay without one
+        let attempts_settings = AttemptsSettings {
+            credentials_path: auth.credentials_path.clone(),
+            max_pin_attempts: auth.max_pin_attempts,
+            max_pattern_attempts: auth.max_pattern_attempts,
+            password_enabled: auth.password_enabled,
+        };
+
         let pin_authentication_page = PinAuthentication::builder()
             .launch(PinAuthenticationSettings {
                 modules: modules.clone(),
                 layout: layout.clone(),
+                credentials_path: auth.credentials_path.clone(),
+                attempts: attempts_settings.clone(),
+                swipe_to_home: settings.transitions.swipe_navigation,
             })
             .forward(
                 sender.input_sender(),
@@ -210,15 +348,69 @@ impl SimpleComponent for LockScreen {
                     info!("auth page message to parent {:?}", msg);
                     match msg {
                        PinAuthenticationMessage::HomeIconPressed => Message::ChangeScreen(Screens::LockScreen),
+                       PinAuthenticationMessage::AttemptsExceeded => Message::AttemptsExceeded,
+                       PinAuthenticationMessage::Authenticated => Message::Unlock,
+                        _ => Message::Dummy
+                    }
+                }),
+            );
+
+        let pattern_authentication_page = PatternAuthentication::builder()
+            .launch(PatternAuthenticationSettings {
+                modules: modules.clone(),
+                credentials_path: auth.credentials_path.clone(),
+                attempts: attempts_settings.clone(),
+                swipe_to_home: settings.transitions.swipe_navigation,
+            })
+            .forward(
+                sender.input_sender(),
+                clone!(@strong modules => move|msg| {
+                    info!("auth page message to parent {:?}", msg);
+                    match msg {
+                        PatternAuthenticationMessage::HomeIconPressed => Message::ChangeScreen(Screens::LockScreen),
+                        PatternAuthenticationMessage::AttemptsExceeded => Message::AttemptsExceeded,
+                        PatternAuthenticationMessage::Authenticated => Message::Unlock,
                         _ => Message::Dummy
                     }
                 }),
             );
 
+        let pin_enrollment_page = PinEnrollment::builder()
+            .launch(PinEnrollmentSettings {
+                modules: modules.clone(),
+                layout: layout.clone(),
+                credentials_path: auth.credentials_path.clone(),
+                max_attempts: auth.max_pin_attempts,
+            })
+            .forward(sender.input_sender(), |msg| {
+                info!("pin enrollment message to parent {:?}", msg);
+                match msg {
+                    PinEnrollmentMessage::Cancelled | PinEnrollmentMessage::Completed => {
+                        Message::PinChangeFinished
+                    }
+                    _ => Message::Dummy,
+                }
+            });
+
+        let users = users::read_login_users(&settings.users).unwrap_or_default();
+        let sessions = match settings.sessions.enabled {
+            true => sessions::read_sessions(&settings.sessions).unwrap_or_default(),
+            false => vec![],
+        };
+
         let password_authentication_page = PasswordAuthentication::builder()
             .launch(PasswordAuthenticationSettings {
                 modules: modules.clone(),
                 layout: layout.clone(),
+                users,
+                last_user: preferences.last_user.clone(),
+                allow_manual_username: settings.users.allow_manual_username,
+                login_timeout: auth.login_timeout,
+                start_session: settings.sessions.enabled,
+                sessions,
+                default_session: settings.sessions.default_session.clone(),
+                session_env: settings.sessions.env.clone(),
+                swipe_to_home: settings.transitions.swipe_navigation,
             })
             .forward(
                 sender.input_sender(),
@@ -226,6 +418,7 @@ impl SimpleComponent for LockScreen {
                     info!("auth page message to parent {:?}", msg);
                     match msg {
                         PasswordAuthenticationMessage::BackPressed => Message::ChangeScreen(Screens::LockScreen),
+                        PasswordAuthenticationMessage::LoggedIn(username) => Message::LoggedIn(username),
                         _ => Message::Dummy
                     }
                 }),
@@ -249,7 +442,26 @@ impl SimpleComponent for LockScreen {
             Option::from(Screens::PasswordScreen.to_string().as_str()),
         );
 
-        let current_screen = Screens::LockScreen;
+        //Adding pattern screeen in stack
+        screens_stack.add_named(
+            pattern_authentication_page.widget(),
+            Option::from(Screens::PatternScreen.to_string().as_str()),
+        );
+
+        //Adding pin enrollment screeen in stack
+        screens_stack.add_named(
+            pin_enrollment_page.widget(),
+            Option::from(Screens::PinEnrollmentScreen.to_string().as_str()),
+        );
+
+        let attempts = Attempts::new(attempts_settings);
+        let current_screen = match args.change_pin {
+            true => Screens::PinEnrollmentScreen,
+            false => unblocked_screen(locked_screen(&auth, &preferences), &auth, &attempts),
+        };
+        if args.change_pin {
+            pin_enrollment_page.emit(PinEnrollmentMessage::Start);
+        }
 
         //Setting current active screen in stack
         screens_stack.set_visible_child_name(&current_screen.to_string());
@@ -257,39 +469,351 @@ impl SimpleComponent for LockScreen {
         //Adding stack to window
         window.set_child(Some(&screens_stack));
 
-        let model = LockScreen {
+        let mut model = LockScreen {
             settings,
             custom_theme,
+            preferences,
             current_screen,
+            transition: TransitionType::None,
+            is_unlocking: false,
+            is_unlocked: false,
+            is_lock_pending: false,
+            lock_state: watch::channel(true).0,
+            dbus_connection: None,
             home_page,
             pin_authentication_page,
             password_authentication_page,
+            pattern_authentication_page,
+            pin_enrollment_page,
+            quit_after_pin_change: args.change_pin,
+            authenticators: vec![],
+            attempts,
+        };
+
+        model.focus_current_screen();
+
+        // the change pin flow on its own neither unlocks nor serves dbus
+        let widgets = AppWidgets {
+            window: window.clone(),
+            screens_stack,
         };
+        if args.change_pin {
+            return ComponentParts { model, widgets };
+        }
+
+        model.start_authenticators(&sender);
 
-        let widgets = AppWidgets { screens_stack };
+        if model.settings.dbus.enabled {
+            if let Ok((resource, conn)) = dbus_service::connect(&model.settings.dbus) {
+                model.dbus_connection = Some(conn.clone());
+                let dbus_sender = sender.input_sender().clone();
+                let is_locked = model.lock_state.subscribe();
+                relm4::spawn(async move {
+                    let _ = dbus_service::serve(resource, conn, dbus_sender, is_locked).await;
+                });
+            }
+        }
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         info!("Update message is {:?}", message);
         match message {
             Message::ChangeScreen(screen) => {
-                self.current_screen = screen;
+                if let Some(method) = screen.auth_method() {
+                    self.preferences.set_method(method);
+                    self.save_preferences();
+                }
+                self.show_screen(screen);
+            }
+            Message::AuthRequested => {
+                let method = preferred_auth_method(&self.settings.auth, &self.preferences);
+                self.show_screen(method.into());
+            }
+            Message::AttemptsExceeded => {
+                info!("too many failed attempts, falling back to password");
+                self.show_screen(Screens::PasswordScreen);
+            }
+            Message::LoggedIn(username) => {
+                self.preferences.last_user = Some(username);
+                self.preferences.set_method(AuthMethod::Password);
+                self.save_preferences();
+                // only the password lifts a pin or pattern lockout
+                self.attempts.reset();
+
+                // when running as greeter, greetd starts the session once we are gone
+                self.unlock();
+            }
+            Message::Lock => {
+                if self.is_unlocking {
+                    self.is_lock_pending = true;
+                    return;
+                }
+                self.lock(&sender);
+            }
+            Message::Unlock => {
+                self.unlock();
+            }
+            Message::UnlockFaded => {
+                self.is_unlocking = false;
+                if !self.is_resident() {
+                    self.quit();
+                    return;
+                }
+                self.is_unlocked = true;
+                if self.is_lock_pending {
+                    self.lock(&sender);
+                }
+            }
+            Message::AuthenticatorEvent(event) => match event {
+                AuthenticatorEvent::Succeeded => self.unlock(),
+                AuthenticatorEvent::Prompt(status)
+                | AuthenticatorEvent::Failed(status)
+                | AuthenticatorEvent::Unavailable(status) => {
+                    self.pin_authentication_page
+                        .emit(PinAuthenticationMessage::AuthenticatorStatus(status));
+                }
+            },
+            Message::ChangePinRequested => {
+                self.pin_enrollment_page.emit(PinEnrollmentMessage::Start);
+                self.show_screen(Screens::PinEnrollmentScreen);
+            }
+            Message::PinChangeFinished => {
+                if self.quit_after_pin_change {
+                    self.quit();
+                    return;
+                }
+                self.show_screen(Screens::LockScreen);
+            }
+            Message::LostDeviceMessageChanged(message) => {
+                self.home_page
+                    .emit(HomePageMessage::LostDeviceMessageChanged(message));
             }
             _ => (),
         }
     }
 
     /// Update the view to represent the updated model.
-    fn update_view(&self, widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+    fn update_view(&self, widgets: &mut Self::Widgets, sender: ComponentSender<Self>) {
         //updating stack screen when current screen changes
+        widgets
+            .screens_stack
+            .set_transition_type(self.transition.into());
         widgets
             .screens_stack
             .set_visible_child_name(self.current_screen.to_string().as_str());
+
+        let is_faded = widgets.screens_stack.has_css_class("fade-out");
+        if self.is_unlocking && !is_faded {
+            fade_out(
+                &widgets.screens_stack,
+                self.settings.transitions.unlock_fade_duration,
+                move || sender.input(Message::UnlockFaded),
+            );
+        }
+
+        // hidden while unlocked, unless the pin is changed over dbus
+        let is_hidden = self.is_unlocked
+            && !matches!(self.current_screen, Screens::PinEnrollmentScreen);
+        if is_hidden && widgets.window.is_visible() {
+            widgets.window.set_visible(false);
+        }
+        // also brings the screens back when locked again right after fading out
+        if !is_hidden && !self.is_unlocking && is_faded {
+            widgets.screens_stack.remove_css_class("fade-out");
+            widgets.screens_stack.set_opacity(1.0);
+        }
+        if !is_hidden && !widgets.window.is_visible() {
+            widgets.window.present();
+        }
     }
 }
 
+impl LockScreen {
+    /// Locking while locked starts over from the first screen
+    fn lock(&mut self, sender: &ComponentSender<Self>) {
+        self.is_lock_pending = false;
+        let screen = locked_screen(&self.settings.auth, &self.preferences);
+        if !self.is_unlocked {
+            self.show_screen(screen);
+            return;
+        }
+
+        info!("locked");
+        self.is_unlocked = false;
+        self.set_locked(true);
+        self.start_authenticators(sender);
+        // the window comes back on the first screen, without a transition
+        self.transition = TransitionType::None;
+        self.current_screen = unblocked_screen(screen, &self.settings.auth, &self.attempts);
+        self.focus_current_screen();
+    }
+
+    /// Unlocking fades the screens out, then the lock screen hides until
+    /// it is locked again over dbus, or quits when it is not resident
+    fn unlock(&mut self) {
+        if self.is_unlocking || self.is_unlocked {
+            return;
+        }
+        info!("unlocked");
+        for authenticator in self.authenticators.iter_mut() {
+            authenticator.stop();
+        }
+        // getting past the lock screen lifts the lock on changing the pin
+        let _ = credentials::save_failed_pin_changes(&self.settings.auth.credentials_path, 0);
+        self.set_locked(false);
+        self.is_unlocking = true;
+    }
+
+    /// Stays around for `Lock` calls when serving dbus, a greeter has
+    /// to go for greetd to start the session
+    fn is_resident(&self) -> bool {
+        self.dbus_connection.is_some() && !self.settings.sessions.enabled
+    }
+
+    fn set_locked(&mut self, is_locked: bool) {
+        self.lock_state.send_replace(is_locked);
+        if let Some(conn) = self.dbus_connection.as_ref() {
+            dbus_service::signal_lock_state(conn, is_locked);
+        }
+    }
+
+    /// Authenticators run next to the pages, the first method to succeed unlocks
+    fn start_authenticators(&mut self, sender: &ComponentSender<Self>) {
+        let mut authenticators = authenticators::from_settings(&self.settings.authenticators);
+        for authenticator in authenticators.iter_mut() {
+            info!("starting authenticator {}", authenticator.name());
+            let input_sender = sender.input_sender().clone();
+            authenticator.start(
+                self.preferences.last_user.clone(),
+                Arc::new(move |event| input_sender.emit(Message::AuthenticatorEvent(event))),
+            );
+        }
+        self.authenticators = authenticators;
+    }
+
+    fn quit(&self) {
+        // the Unlocked signal is still queued otherwise
+        if let Some(conn) = self.dbus_connection.as_ref() {
+            dbus_service::flush(conn);
+        }
+        relm4::main_application().quit();
+    }
+
+    /// Switches to the screen with the transition configured
+    /// for going from the current screen to it
+    fn show_screen(&mut self, screen: Screens) {
+        let screen = unblocked_screen(screen, &self.settings.auth, &self.attempts);
+        self.transition =
+            transition_between(&self.settings.transitions, &self.current_screen, &screen);
+        self.current_screen = screen;
+        self.focus_current_screen();
+    }
+
+    /// Moves keyboard focus into the visible auth page
+    /// so typing works without tapping it first
+    fn focus_current_screen(&self) {
+        match self.current_screen {
+            Screens::PinScreen => self
+                .pin_authentication_page
+                .emit(PinAuthenticationMessage::Focus),
+            Screens::PasswordScreen => self
+                .password_authentication_page
+                .emit(PasswordAuthenticationMessage::Focus),
+            Screens::PatternScreen => self
+                .pattern_authentication_page
+                .emit(PatternAuthenticationMessage::Focus),
+            Screens::PinEnrollmentScreen => self
+                .pin_enrollment_page
+                .emit(PinEnrollmentMessage::Focus),
+            Screens::LockScreen => (),
+        }
+    }
+
+    fn save_preferences(&self) {
+        let auth = &self.settings.auth;
+        if !auth.remember_last_method {
+            return;
+        }
+        let _ = preferences::write_preferences_yml(&auth.preferences_path, &self.preferences);
+    }
+}
+
+/// Transition for going from one screen to another
+fn transition_between(
+    transitions: &TransitionsSettings,
+    from: &Screens,
+    to: &Screens,
+) -> TransitionType {
+    match (from.auth_method(), to.auth_method()) {
+        (None, Some(_)) => transitions.home_to_auth,
+        (Some(_), None) => transitions.auth_to_home,
+        (Some(from), Some(to)) if from != to => transitions.between_auth,
+        _ => TransitionType::None,
+    }
+}
+
+/// Screen shown when the lock screen comes up
+fn locked_screen(auth: &AuthSettings, preferences: &AuthPreferences) -> Screens {
+    match auth.show_home_page {
+        true => Screens::LockScreen,
+        false => preferred_auth_method(auth, preferences).into(),
+    }
+}
+
+/// The password screen in place of the pin and pattern screens
+/// while they are locked out
+fn unblocked_screen(screen: Screens, auth: &AuthSettings, attempts: &Attempts) -> Screens {
+    let is_blocked = matches!(screen, Screens::PinScreen | Screens::PatternScreen)
+        && auth.password_enabled
+        && attempts.lockout() == Lockout::UntilPassword;
+    match is_blocked {
+        true => Screens::PasswordScreen,
+        false => screen,
+    }
+}
+
+/// Fades the widget out over `duration` milliseconds, then calls `on_faded`
+/// on the next frame. The `fade-out` class stays set until the widget is
+/// shown again so themes can style it
+fn fade_out(widget: &gtk::Stack, duration: u32, on_faded: impl Fn() + 'static) {
+    widget.add_css_class("fade-out");
+    let duration = duration.max(1) as f64 * 1000.0; // frame times are in microseconds
+    let started_at: Cell<Option<i64>> = Cell::new(None);
+    widget.add_tick_callback(move |widget, frame_clock| {
+        let now = frame_clock.frame_time();
+        let start = started_at.get().unwrap_or(now);
+        started_at.set(Some(start));
+        let progress = ((now - start) as f64 / duration).min(1.0);
+        widget.set_opacity(1.0 - progress);
+        if progress < 1.0 {
+            return glib::ControlFlow::Continue;
+        }
+        on_faded();
+        glib::ControlFlow::Break
+    });
+}
+
+/// Picks the method to show first: the one remembered for the last user,
+/// else the configured default, skipping methods that are not enabled
+fn preferred_auth_method(auth: &AuthSettings, preferences: &AuthPreferences) -> AuthMethod {
+    let method = match auth.remember_last_method {
+        true => preferences
+            .last_user_method()
+            .unwrap_or(auth.default_method),
+        false => auth.default_method,
+    };
+
+    if auth.is_enabled(method) {
+        return method;
+    }
+    [AuthMethod::Pin, AuthMethod::Pattern, AuthMethod::Password]
+        .into_iter()
+        .find(|method| auth.is_enabled(*method))
+        .unwrap_or(method)
+}
+
 fn main() {
     // Enables logger
     // install global collector configured based on RUST_LOG env var.
@@ -298,6 +822,201 @@ fn main() {
         .with_env_filter("mecha_lock_screen=trace")
         .with_thread_names(true)
         .init();
+    let args = Args::parse(std::env::args().skip(1));
+    if args.set_pattern {
+        let settings = settings::read_settings_yml().unwrap_or_default();
+        if set_pattern(&settings).is_err() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app = RelmApp::new("lock.screen").with_args(vec![]);
-    app.run::<LockScreen>(());
+    app.run::<LockScreen>(args);
+}
+
+/// # Set Pattern
+///
+/// Stores the pattern read from stdin as the dots it connects,
+/// numbered 0 to 8 row by row. Once a PIN or pattern is stored, either
+/// of them has to come first on a line of its own, e.g.
+/// `printf '1590\n0148\n' | mecha_lock_screen --set-pattern`
+fn set_pattern(settings: &LockScreenSettings) -> Result<()> {
+    let auth = &settings.auth;
+    let credentials = match Path::new(&auth.credentials_path).exists() {
+        true => credentials::read_credentials_yml(&auth.credentials_path)?,
+        false => Credentials::default(),
+    };
+    let mut lines = io::stdin().lines().map_while(|line| line.ok());
+
+    // the same lockout as on the lock screen, guessing here is no easier
+    if credentials.pin.is_some() || credentials.pattern.is_some() {
+        let attempts = Attempts::new(AttemptsSettings {
+            credentials_path: auth.credentials_path.clone(),
+            max_pin_attempts: auth.max_pin_attempts,
+            max_pattern_attempts: auth.max_pattern_attempts,
+            password_enabled: auth.password_enabled,
+        });
+        if attempts.lockout() != Lockout::None {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::WrongCurrentCredentials,
+                "too many wrong pins or patterns, unlock the device first".to_string(),
+            ));
+        }
+
+        let current = lines.next().unwrap_or_default();
+        let is_current = credentials
+            .pin
+            .iter()
+            .chain(credentials.pattern.iter())
+            .any(|hash| credentials::verify_secret(current.trim(), hash));
+        if !is_current {
+            let method = match credentials.pattern {
+                Some(_) => AuthMethod::Pattern,
+                None => AuthMethod::Pin,
+            };
+            attempts.failed(method);
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::WrongCurrentCredentials,
+                "the current pin or pattern is wrong".to_string(),
+            ));
+        }
+    }
+
+    let pattern = lines.next().unwrap_or_default();
+    let min_length = settings.modules.pattern_configs.min_length;
+    let nodes = pattern_from_string(pattern.trim()).filter(|nodes| nodes.len() >= min_length);
+    let Some(nodes) = nodes else {
+        bail!(LockScreenError::new(
+            LockScreenErrorCodes::InvalidPattern,
+            format!(
+                "a pattern connects at least {} of the dots 0 to 8, each once",
+                min_length
+            ),
+        ));
+    };
+
+    credentials::save_pattern(&auth.credentials_path, &pattern_to_string(&nodes))?;
+    info!(task = "set pattern", "pattern changed");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_method_is_used_without_preferences() {
+        let auth = AuthSettings {
+            default_method: AuthMethod::Password,
+            ..AuthSettings::default()
+        };
+
+        assert_eq!(
+            preferred_auth_method(&auth, &AuthPreferences::default()),
+            AuthMethod::Password
+        );
+    }
+
+    #[test]
+    fn last_method_of_last_user_is_preferred() {
+        let auth = AuthSettings::default();
+        let mut preferences = AuthPreferences::default();
+        preferences.last_user = Some("user".to_string());
+        preferences.set_method(AuthMethod::Password);
+
+        assert_eq!(
+            preferred_auth_method(&auth, &preferences),
+            AuthMethod::Password
+        );
+
+        let auth = AuthSettings {
+            remember_last_method: false,
+            ..AuthSettings::default()
+        };
+        assert_eq!(preferred_auth_method(&auth, &preferences), AuthMethod::Pin);
+    }
+
+    #[test]
+    fn disabled_methods_are_skipped() {
+        let auth = AuthSettings {
+            default_method: AuthMethod::Pin,
+            pin_enabled: false,
+            ..AuthSettings::default()
+        };
+
+        assert_eq!(
+            preferred_auth_method(&auth, &AuthPreferences::default()),
+            AuthMethod::Password
+        );
+    }
+
+    #[test]
+    fn disabled_pattern_falls_back_to_an_enabled_method() {
+        let auth = AuthSettings {
+            default_method: AuthMethod::Pattern,
+            pattern_enabled: false,
+            ..AuthSettings::default()
+        };
+
+        assert_eq!(
+            preferred_auth_method(&auth, &AuthPreferences::default()),
+            AuthMethod::Pin
+        );
+
+        let auth = AuthSettings {
+            pattern_enabled: true,
+            ..auth
+        };
+        assert_eq!(
+            preferred_auth_method(&auth, &AuthPreferences::default()),
+            AuthMethod::Pattern
+        );
+    }
+
+    #[test]
+    fn transitions_follow_the_screens_involved() {
+        let transitions = TransitionsSettings::default();
+
+        assert_eq!(
+            transition_between(&transitions, &Screens::LockScreen, &Screens::PinScreen),
+            transitions.home_to_auth
+        );
+        assert_eq!(
+            transition_between(&transitions, &Screens::PatternScreen, &Screens::LockScreen),
+            transitions.auth_to_home
+        );
+        assert_eq!(
+            transition_between(&transitions, &Screens::PinScreen, &Screens::PasswordScreen),
+            transitions.between_auth
+        );
+        assert_eq!(
+            transition_between(&transitions, &Screens::PinScreen, &Screens::PinScreen),
+            TransitionType::None
+        );
+    }
+
+    #[test]
+    fn change_pin_flag_is_parsed() {
+        let args = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(args(&[]), Args::default());
+        assert!(args(&["--change-pin"]).change_pin);
+        assert!(!args(&["--unknown"]).change_pin);
+        assert!(args(&["--set-pattern"]).set_pattern);
+    }
+
+    #[test]
+    fn auth_screens_map_to_their_method() {
+        assert_eq!(Screens::LockScreen.auth_method(), None);
+        assert_eq!(Screens::PinScreen.auth_method(), Some(AuthMethod::Pin));
+        assert_eq!(
+            Screens::PasswordScreen.auth_method(),
+            Some(AuthMethod::Password)
+        );
+        assert_eq!(
+            Screens::PatternScreen.auth_method(),
+            Some(AuthMethod::Pattern)
+        );
+    }
 }
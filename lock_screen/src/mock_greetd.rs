@@ -0,0 +1,120 @@
+use std::{
+    io::Read,
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    thread::{self, JoinHandle},
+};
+
+use greetd_ipc::{codec::SyncCodec, AuthMessageType, ErrorType, Request, Response};
+
+/// # Step
+///
+/// One exchange of a scripted conversation, the server
+/// reads the next request and then acts on it
+pub enum Step {
+    Reply(Response),
+    Stall,      // Say nothing until the client hangs up, to run into timeouts
+    Disconnect, // Hang up mid-conversation
+}
+
+/// # Mock Greetd
+///
+/// Stand-in for the greetd IPC server that plays a script against the
+/// client on its own thread, recording the requests it receives. Once the
+/// script is done it only answers `CancelSession`, until the client hangs up
+pub struct MockGreetd {
+    handle: JoinHandle<Vec<Request>>,
+}
+
+impl MockGreetd {
+    /// Starts a conversation over a socket pair, the returned
+    /// stream is the client side
+    pub fn start(script: Vec<Step>) -> (Self, UnixStream) {
+        let (client, server) = UnixStream::pair().expect("unable to create socket pair");
+        let handle = thread::spawn(move || run_script(server, script));
+        (Self { handle }, client)
+    }
+
+    /// Listens on a socket path and plays the script
+    /// with the first client that connects
+    pub fn listen(path: &Path, script: Vec<Step>) -> Self {
+        let listener = UnixListener::bind(path).expect("unable to bind mock greetd socket");
+        let handle = thread::spawn(move || match listener.accept() {
+            Ok((server, _)) => run_script(server, script),
+            Err(_) => vec![],
+        });
+        Self { handle }
+    }
+
+    /// Waits for the client to hang up and returns the requests received
+    pub fn requests(self) -> Vec<Request> {
+        self.handle.join().expect("mock greetd panicked")
+    }
+}
+
+fn run_script(mut server: UnixStream, script: Vec<Step>) -> Vec<Request> {
+    let mut requests = vec![];
+    for step in script {
+        match Request::read_from(&mut server) {
+            Ok(request) => requests.push(request),
+            Err(_) => return requests,
+        }
+        match step {
+            Step::Reply(response) => {
+                if response.write_to(&mut server).is_err() {
+                    return requests;
+                }
+            }
+            Step::Stall => {
+                // the client gives up on its own, reading only sees it hang up
+                let _ = server.read(&mut [0; 1]);
+                return requests;
+            }
+            Step::Disconnect => return requests,
+        }
+    }
+
+    while let Ok(request) = Request::read_from(&mut server) {
+        let is_cancel_session = matches!(request, Request::CancelSession);
+        requests.push(request);
+        if !is_cancel_session || Response::Success.write_to(&mut server).is_err() {
+            break;
+        }
+    }
+    requests
+}
+
+pub fn secret_prompt(message: &str) -> Response {
+    Response::AuthMessage {
+        auth_message_type: AuthMessageType::Secret,
+        auth_message: message.to_string(),
+    }
+}
+
+pub fn visible_prompt(message: &str) -> Response {
+    Response::AuthMessage {
+        auth_message_type: AuthMessageType::Visible,
+        auth_message: message.to_string(),
+    }
+}
+
+pub fn info_message(message: &str) -> Response {
+    Response::AuthMessage {
+        auth_message_type: AuthMessageType::Info,
+        auth_message: message.to_string(),
+    }
+}
+
+pub fn auth_error(description: &str) -> Response {
+    Response::Error {
+        error_type: ErrorType::AuthError,
+        description: description.to_string(),
+    }
+}
+
+pub fn error(description: &str) -> Response {
+    Response::Error {
+        error_type: ErrorType::Error,
+        description: description.to_string(),
+    }
+}
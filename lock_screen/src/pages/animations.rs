@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use gtk::{
+    glib,
+    prelude::{Cast, IsA, WidgetExt},
+};
+use relm4::gtk;
+
+/// Matches the `shake` animation in the stylesheet
+const SHAKE_DURATION: Duration = Duration::from_millis(400);
+
+/// # Shake
+///
+/// Plays the `shake` css animation on the widget, the class is taken
+/// off again once done so the next failure shakes it again
+pub fn shake(widget: &impl IsA<gtk::Widget>) {
+    let widget = widget.clone().upcast::<gtk::Widget>();
+    if widget.has_css_class("shake") {
+        return;
+    }
+    widget.add_css_class("shake");
+    glib::timeout_add_local_once(SHAKE_DURATION, move || {
+        widget.remove_css_class("shake");
+    });
+}
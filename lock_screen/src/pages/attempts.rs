@@ -0,0 +1,210 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    credentials::{self, UnlockAttempts},
+    settings::AuthMethod,
+};
+
+/// Seconds PIN and pattern wait for once they ran out without a
+/// password to fall back to, doubling with every further wrong attempt
+const BACKOFF_DELAY: u64 = 30;
+
+/// Longest wait between two attempts
+const MAX_BACKOFF_DELAY: u64 = 3600;
+
+//Init Settings
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub credentials_path: String,
+    pub max_pin_attempts: u32,     // Wrong PINs in a row before running out, 0 disables
+    pub max_pattern_attempts: u32, // Wrong patterns in a row before running out, 0 disables
+    pub password_enabled: bool,    // Running out waits for a password unlock, else for a delay
+}
+
+/// Whether a PIN or pattern may be tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lockout {
+    None,
+    // Seconds left before the next attempt
+    Delayed(u64),
+    // Only a password unlock lets PIN and pattern be tried again
+    UntilPassword,
+}
+
+/// # Attempts
+///
+/// Wrong PINs and patterns in a row, kept in the credentials file so
+/// neither reopening a page nor restarting the lock screen gives fresh
+/// guesses. Running out of either blocks both
+#[derive(Debug)]
+pub struct Attempts {
+    settings: Settings,
+}
+
+impl Attempts {
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
+    }
+
+    pub fn lockout(&self) -> Lockout {
+        lockout(&self.read(), &self.settings, now())
+    }
+
+    /// Counts a wrong attempt, giving the lockout it leads to
+    pub fn failed(&self, method: AuthMethod) -> Lockout {
+        let now = now();
+        let attempts = count_failure(self.read(), method, &self.settings, now);
+        let _ = credentials::save_unlock_attempts(&self.settings.credentials_path, &attempts);
+        lockout(&attempts, &self.settings, now)
+    }
+
+    /// Starts the count over, once PIN or pattern unlocked, or a password
+    /// unlock lifted the lockout
+    pub fn reset(&self) {
+        let _ = credentials::save_unlock_attempts(
+            &self.settings.credentials_path,
+            &UnlockAttempts::default(),
+        );
+    }
+
+    fn read(&self) -> UnlockAttempts {
+        credentials::read_credentials_yml(&self.settings.credentials_path)
+            .map(|credentials| credentials.unlock_attempts)
+            .unwrap_or_default()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+fn lockout(attempts: &UnlockAttempts, settings: &Settings, now: u64) -> Lockout {
+    if excess(attempts, settings).is_none() {
+        return Lockout::None;
+    }
+    if settings.password_enabled {
+        return Lockout::UntilPassword;
+    }
+    match attempts.locked_until > now {
+        true => Lockout::Delayed(attempts.locked_until - now),
+        false => Lockout::None,
+    }
+}
+
+/// Wrong attempts past the maximum of the method furthest past
+/// it, None while neither ran out
+fn excess(attempts: &UnlockAttempts, settings: &Settings) -> Option<u32> {
+    [
+        (attempts.failed_pins, settings.max_pin_attempts),
+        (attempts.failed_patterns, settings.max_pattern_attempts),
+    ]
+    .into_iter()
+    .filter(|(failed, max)| *max > 0 && failed >= max)
+    .map(|(failed, max)| failed - max)
+    .max()
+}
+
+fn count_failure(
+    mut attempts: UnlockAttempts,
+    method: AuthMethod,
+    settings: &Settings,
+    now: u64,
+) -> UnlockAttempts {
+    match method {
+        AuthMethod::Pattern => {
+            attempts.failed_patterns = attempts.failed_patterns.saturating_add(1)
+        }
+        _ => attempts.failed_pins = attempts.failed_pins.saturating_add(1),
+    }
+    if let Some(excess) = excess(&attempts, settings) {
+        let delay = BACKOFF_DELAY
+            .saturating_mul(1 << excess.min(16))
+            .min(MAX_BACKOFF_DELAY);
+        attempts.locked_until = now + delay;
+    }
+    attempts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(password_enabled: bool) -> Settings {
+        Settings {
+            credentials_path: "".to_string(),
+            max_pin_attempts: 3,
+            max_pattern_attempts: 5,
+            password_enabled,
+        }
+    }
+
+    fn fail(
+        attempts: UnlockAttempts,
+        method: AuthMethod,
+        times: u32,
+        settings: &Settings,
+    ) -> UnlockAttempts {
+        (0..times).fold(attempts, |attempts, _| {
+            count_failure(attempts, method, settings, 1000)
+        })
+    }
+
+    #[test]
+    fn running_out_waits_for_the_password() {
+        let settings = settings(true);
+
+        let attempts = fail(UnlockAttempts::default(), AuthMethod::Pin, 2, &settings);
+        assert_eq!(lockout(&attempts, &settings, 1000), Lockout::None);
+        let attempts = fail(attempts, AuthMethod::Pin, 1, &settings);
+
+        // no amount of waiting lifts it
+        assert_eq!(
+            lockout(&attempts, &settings, u64::MAX),
+            Lockout::UntilPassword
+        );
+    }
+
+    #[test]
+    fn running_out_of_pins_blocks_patterns() {
+        let settings = settings(true);
+
+        let attempts = fail(UnlockAttempts::default(), AuthMethod::Pattern, 4, &settings);
+        let attempts = fail(attempts, AuthMethod::Pin, 3, &settings);
+
+        assert_eq!(attempts.failed_patterns, 4);
+        assert_eq!(lockout(&attempts, &settings, 1000), Lockout::UntilPassword);
+    }
+
+    #[test]
+    fn without_password_attempts_wait_longer_each_time() {
+        let settings = settings(false);
+
+        let attempts = fail(UnlockAttempts::default(), AuthMethod::Pin, 3, &settings);
+        assert_eq!(lockout(&attempts, &settings, 1000), Lockout::Delayed(30));
+        assert_eq!(lockout(&attempts, &settings, 1030), Lockout::None);
+
+        let attempts = fail(attempts, AuthMethod::Pin, 1, &settings);
+        assert_eq!(lockout(&attempts, &settings, 1000), Lockout::Delayed(60));
+
+        let attempts = fail(attempts, AuthMethod::Pin, 20, &settings);
+        assert_eq!(
+            lockout(&attempts, &settings, 1000),
+            Lockout::Delayed(MAX_BACKOFF_DELAY)
+        );
+    }
+
+    #[test]
+    fn zero_max_attempts_never_runs_out() {
+        let settings = Settings {
+            max_pin_attempts: 0,
+            ..settings(true)
+        };
+
+        let attempts = fail(UnlockAttempts::default(), AuthMethod::Pin, 100, &settings);
+
+        assert_eq!(lockout(&attempts, &settings, 1000), Lockout::None);
+    }
+}
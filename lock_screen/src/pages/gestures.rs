@@ -0,0 +1,35 @@
+use gtk::prelude::{GestureSwipeExt, IsA, WidgetExt};
+use relm4::gtk;
+
+/// Minimum velocity (px/s) for a swipe to count
+const SWIPE_VELOCITY: f64 = 400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwipeDirection {
+    Up,
+    Down,
+}
+
+/// # Add Swipe Controller
+///
+/// Calls `on_swipe` when the widget is swiped mostly vertically
+/// in the given direction, by touch or pointer
+pub fn add_swipe_controller<F>(widget: &impl IsA<gtk::Widget>, direction: SwipeDirection, on_swipe: F)
+where
+    F: Fn() + 'static,
+{
+    let swipe_gesture = gtk::GestureSwipe::builder().touch_only(false).build();
+    swipe_gesture.connect_swipe(move |_, velocity_x, velocity_y| {
+        if velocity_y.abs() < SWIPE_VELOCITY || velocity_y.abs() < velocity_x.abs() {
+            return;
+        }
+        let is_direction = match direction {
+            SwipeDirection::Up => velocity_y < 0.0,
+            SwipeDirection::Down => velocity_y > 0.0,
+        };
+        if is_direction {
+            on_swipe();
+        }
+    });
+    widget.add_controller(swipe_gesture);
+}
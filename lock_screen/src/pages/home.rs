@@ -1,32 +1,124 @@
-use gtk::{gdk, gio, glib::clone, prelude::*, subclass::*};
+use std::time::Duration;
+
+use chrono::Local;
+use gtk::{gdk::Display, gio, glib, glib::clone, prelude::*};
 use relm4::{
-    gtk::{self, gdk::BUTTON_PRIMARY, GestureClick},
-    Component, ComponentController, ComponentParts, ComponentSender, Controller, SimpleComponent,
+    gtk::{self, CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION},
+    Component, ComponentController, ComponentParts, ComponentSender, Controller, RelmWidgetExt,
+    SimpleComponent,
 };
 
-use crate::Screens;
+use super::gestures::{self, SwipeDirection};
+use crate::{
+    errors::{LockScreenError, LockScreenErrorCodes},
+    flashlight,
+    settings::{
+        BatteryModule, ClockModule, EmergencyModule, FlashlightModule, OwnerMessageModule,
+        WifiModule,
+    },
+    theme::{Background, BackgroundFillType},
+    Screens,
+};
+use custom_utils::get_image_from_path;
 use custom_widgets::icon_button::{
     IconButton, IconButtonCss, InitSettings as IconButtonStetings,
-    InputMessage as IconButtonInputMessage, OutputMessage as IconButtonOutputMessage,
+    OutputMessage as IconButtonOutputMessage,
 };
+use grpc_clients::{battery_client::BatteryManagerClient, network_client::NetworkManagerClient};
 use tracing::info;
 
+/// Interval at which wifi and battery state is refreshed
+const DEVICE_STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct Settings {
     pub lock_icon: Option<String>,
     pub unlock_icon: Option<String>,
     pub password_icon: Option<String>,
+    pub pattern_icon: Option<String>,
+    pub pin_enabled: bool,
+    pub password_enabled: bool,
+    pub pattern_enabled: bool,
+    pub swipe_navigation: bool,
+    pub clock: ClockModule,
+    pub wifi: WifiModule,
+    pub battery: BatteryModule,
+    pub owner_message: OwnerMessageModule,
+    pub flashlight: FlashlightModule,
+    pub emergency: EmergencyModule,
+    pub background: Option<Background>,
 }
 
-pub struct HomePage {}
+pub struct HomePage {
+    settings: Settings,
+    current_time: String,
+    current_date: String,
+    wifi_state: WifiState,
+    battery_state: BatteryState,
+    lost_device_message: Option<String>,
+    is_flashlight_on: bool,
+    is_emergency_visible: bool,
+}
 
 pub struct HomePageWidgets {
+    clock_label: gtk::Label,
+    date_label: gtk::Label,
+    wifi_image: gtk::Image,
+    battery_image: gtk::Image,
+    // icons shown now, the images are only reloaded when they change
+    wifi_icon: Option<String>,
+    battery_icon: Option<String>,
+    lost_device_box: gtk::Box,
+    lost_device_label: gtk::Label,
+    emergency_revealer: gtk::Revealer,
+    flashlight_btn: Controller<IconButton>,
+    emergency_btn: Controller<IconButton>,
     password_screen_btn: Controller<IconButton>,
+    pattern_screen_btn: Controller<IconButton>,
     unlock_btn: Controller<IconButton>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum WifiConnectedState {
+    Low,
+    Weak,
+    Good,
+    Strong,
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub enum WifiState {
+    On,
+    #[default]
+    Off,
+    Connected(WifiConnectedState),
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub enum BatteryState {
+    #[default]
+    Level0,
+    Level10,
+    Level20,
+    Level30,
+    Level40,
+    Level50,
+    Level60,
+    Level70,
+    Level80,
+    Level90,
+    Level100,
+}
+
 #[derive(Debug)]
 pub enum Message {
     ChangeScreen(Screens),
+    AuthRequested,
+    TimeTick,
+    WifiStateUpdate(WifiState),
+    BatteryStateUpdate(BatteryState),
+    LostDeviceMessageChanged(Option<String>),
+    FlashlightToggled,
+    EmergencyToggled,
 }
 
 impl SimpleComponent for HomePage {
@@ -41,11 +133,148 @@ impl SimpleComponent for HomePage {
         root: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        if let Some(background) = init.background.as_ref() {
+            apply_background(background);
+        }
+
+        let status_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .css_classes(["home-status"])
+            .halign(gtk::Align::End)
+            .spacing(12)
+            .build();
+
+        let wifi_image = get_image_from_path(init.wifi.icon.off.to_owned(), &["home-status-icon"]);
+        let battery_image =
+            get_image_from_path(init.battery.icon.level_0.to_owned(), &["home-status-icon"]);
+        status_box.append(&wifi_image);
+        status_box.append(&battery_image);
+
+        let current_time = format_now(&init.clock.time_format);
+        let current_date = format_now(&init.clock.date_format);
+
+        let clock_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["home-clock-box"])
+            .valign(gtk::Align::Center)
+            .vexpand(true)
+            .build();
+
+        let clock_label = gtk::Label::builder()
+            .label(&current_time)
+            .css_classes(["home-clock"])
+            .build();
+
+        let date_label = gtk::Label::builder()
+            .label(&current_date)
+            .css_classes(["home-date"])
+            .build();
+
+        clock_box.append(&clock_label);
+        clock_box.append(&date_label);
+
+        // set remotely over dbus, hidden until a message arrives
+        let lost_device_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["home-lost-device"])
+            .visible(false)
+            .build();
+        let lost_device_title = gtk::Label::builder()
+            .label("This device has been reported lost")
+            .css_classes(["home-lost-device-title"])
+            .build();
+        let lost_device_label = gtk::Label::builder()
+            .css_classes(["home-lost-device-message"])
+            .wrap(true)
+            .justify(gtk::Justification::Center)
+            .build();
+        lost_device_box.append(&lost_device_title);
+        lost_device_box.append(&lost_device_label);
+        clock_box.append(&lost_device_box);
+
+        let owner_message = &init.owner_message;
+        if owner_message.enabled {
+            let owner_box = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .css_classes(["home-owner-message"])
+                .build();
+            let owner_title = gtk::Label::builder()
+                .label(&owner_message.title)
+                .css_classes(["home-owner-message-title"])
+                .build();
+            let owner_label = gtk::Label::builder()
+                .label(&owner_message.message)
+                .css_classes(["home-owner-message-text"])
+                .wrap(true)
+                .justify(gtk::Justification::Center)
+                .build();
+            owner_box.append(&owner_title);
+            owner_box.append(&owner_label);
+            clock_box.append(&owner_box);
+        }
+
+        let actions_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .css_classes(["home-actions"])
+            .halign(gtk::Align::Center)
+            .spacing(24)
+            .build();
+
+        let flashlight_btn = IconButton::builder()
+            .launch(IconButtonStetings {
+                icon: init.flashlight.icon.default.to_owned(),
+                toggle_icon: None,
+                css: IconButtonCss::default(),
+            })
+            .forward(sender.input_sender(), |msg| match msg {
+                IconButtonOutputMessage::Clicked => Message::FlashlightToggled,
+            });
+        flashlight_btn.widget().set_visible(init.flashlight.enabled);
+
+        let emergency_btn = IconButton::builder()
+            .launch(IconButtonStetings {
+                icon: init.emergency.icon.default.to_owned(),
+                toggle_icon: None,
+                css: IconButtonCss::default(),
+            })
+            .forward(sender.input_sender(), |msg| match msg {
+                IconButtonOutputMessage::Clicked => Message::EmergencyToggled,
+            });
+        emergency_btn.widget().set_visible(init.emergency.enabled);
+
+        actions_box.append(flashlight_btn.widget());
+        actions_box.append(emergency_btn.widget());
+        actions_box.set_visible(init.flashlight.enabled || init.emergency.enabled);
+
+        let emergency_contacts_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["home-emergency-contacts"])
+            .halign(gtk::Align::Center)
+            .spacing(8)
+            .build();
+        for contact in init.emergency.contacts.iter() {
+            let contact_label = gtk::Label::builder()
+                .label(&format!("{}  {}", contact.name, contact.number))
+                .css_classes(["home-emergency-contact"])
+                .selectable(true)
+                .build();
+            emergency_contacts_box.append(&contact_label);
+        }
+        let emergency_revealer = gtk::Revealer::builder()
+            .transition_type(gtk::RevealerTransitionType::SlideUp)
+            .child(&emergency_contacts_box)
+            .build();
+
+        let swipe_hint_label = gtk::Label::builder()
+            .label("Swipe up to unlock")
+            .css_classes(["home-swipe-hint"])
+            .visible(init.swipe_navigation)
+            .build();
+
         let footer = gtk::Box::builder()
             .orientation(gtk::Orientation::Horizontal)
             .css_classes(["footer"])
             .valign(gtk::Align::End)
-            .vexpand(true)
             .build();
 
         let password_screen_btn = IconButton::builder()
@@ -63,6 +292,21 @@ impl SimpleComponent for HomePage {
                 }
             });
 
+        let pattern_screen_btn = IconButton::builder()
+            .launch(IconButtonStetings {
+                icon: init.pattern_icon.to_owned(),
+                toggle_icon: None,
+                css: IconButtonCss::default(),
+            })
+            .forward(sender.input_sender(), |msg| {
+                info!("msg is {:?}", msg);
+                match msg {
+                    IconButtonOutputMessage::Clicked => {
+                        Message::ChangeScreen(Screens::PatternScreen)
+                    }
+                }
+            });
+
         let unlock_btn = IconButton::builder()
             .launch(IconButtonStetings {
                 icon: init.lock_icon.to_owned(),
@@ -79,13 +323,77 @@ impl SimpleComponent for HomePage {
         unlock_btn_widget.set_hexpand(true);
         unlock_btn_widget.set_halign(gtk::Align::End);
 
+        // only offer the methods that are configured
+        password_screen_btn.widget().set_visible(init.password_enabled);
+        pattern_screen_btn.widget().set_visible(init.pattern_enabled);
+        unlock_btn_widget.set_visible(init.pin_enabled);
+
         footer.append(password_screen_btn.widget());
+        footer.append(pattern_screen_btn.widget());
         footer.append(unlock_btn_widget);
+
+        root.append(&status_box);
+        root.append(&clock_box);
+        root.append(&emergency_revealer);
+        root.append(&actions_box);
+        root.append(&swipe_hint_label);
         root.append(&footer);
 
-        let model = HomePage {};
+        // swipe up from anywhere on the home page to reach the preferred authentication
+        if init.swipe_navigation {
+            gestures::add_swipe_controller(
+                root,
+                SwipeDirection::Up,
+                clone!(@strong sender => move || sender.input(Message::AuthRequested)),
+            );
+        }
+
+        glib::timeout_add_seconds_local(
+            1,
+            clone!(@strong sender => move || {
+                sender.input(Message::TimeTick);
+                glib::ControlFlow::Continue
+            }),
+        );
+
+        let status_sender = sender.clone();
+        relm4::spawn(async move {
+            let mut is_wifi_unavailable = false;
+            let mut is_battery_unavailable = false;
+            loop {
+                let wifi_state = state_or_default(get_wifi_data().await, &mut is_wifi_unavailable);
+                let battery_state =
+                    state_or_default(get_battery_data().await, &mut is_battery_unavailable);
+                status_sender.input(Message::WifiStateUpdate(wifi_state));
+                status_sender.input(Message::BatteryStateUpdate(battery_state));
+                relm4::tokio::time::sleep(DEVICE_STATUS_REFRESH_INTERVAL).await;
+            }
+        });
+
+        let model = HomePage {
+            settings: init,
+            current_time,
+            current_date,
+            wifi_state: WifiState::default(),
+            battery_state: BatteryState::default(),
+            lost_device_message: None,
+            is_flashlight_on: false,
+            is_emergency_visible: false,
+        };
         let widgets = HomePageWidgets {
+            clock_label,
+            date_label,
+            wifi_icon: model.settings.wifi.icon.off.clone(),
+            battery_icon: model.settings.battery.icon.level_0.clone(),
+            wifi_image,
+            battery_image,
+            lost_device_box,
+            lost_device_label,
+            emergency_revealer,
+            flashlight_btn,
+            emergency_btn,
             password_screen_btn,
+            pattern_screen_btn,
             unlock_btn,
         };
         ComponentParts { model, widgets }
@@ -96,19 +404,245 @@ impl SimpleComponent for HomePage {
             Message::ChangeScreen(screen) => {
                 sender.output_sender().send(Message::ChangeScreen(screen));
             }
+            Message::AuthRequested => {
+                sender.output_sender().send(Message::AuthRequested);
+            }
+            Message::TimeTick => {
+                self.current_time = format_now(&self.settings.clock.time_format);
+                self.current_date = format_now(&self.settings.clock.date_format);
+            }
+            Message::WifiStateUpdate(state) => {
+                self.wifi_state = state;
+            }
+            Message::BatteryStateUpdate(state) => {
+                self.battery_state = state;
+            }
+            Message::LostDeviceMessageChanged(message) => {
+                self.lost_device_message = message;
+            }
+            Message::FlashlightToggled => {
+                self.is_flashlight_on = !self.is_flashlight_on;
+                flashlight::set_flashlight(
+                    &self.settings.flashlight.device_path,
+                    self.is_flashlight_on,
+                );
+            }
+            Message::EmergencyToggled => {
+                self.is_emergency_visible = !self.is_emergency_visible;
+            }
         }
     }
 
     fn init_root() -> Self::Root {
         gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
-            .css_classes(["home-container"])
+            .css_classes(["home-container", "home-background"])
             .build()
     }
 
-    fn update_view(&self, _widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {}
+    fn update_view(&self, widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+        widgets.clock_label.set_label(&self.current_time);
+        widgets.date_label.set_label(&self.current_date);
+
+        match self.lost_device_message.as_ref() {
+            Some(message) => {
+                widgets.lost_device_label.set_label(message);
+                widgets.lost_device_box.set_visible(true);
+            }
+            None => widgets.lost_device_box.set_visible(false),
+        }
+        widgets
+            .emergency_revealer
+            .set_reveal_child(self.is_emergency_visible);
+        widgets
+            .flashlight_btn
+            .widget()
+            .set_class_active("home-action-active", self.is_flashlight_on);
+
+        let wifi_icons = &self.settings.wifi.icon;
+        let wifi_icon = match self.wifi_state {
+            WifiState::Off => wifi_icons.off.clone(),
+            WifiState::On => wifi_icons.on.clone(),
+            WifiState::Connected(WifiConnectedState::Low) => wifi_icons.low.clone(),
+            WifiState::Connected(WifiConnectedState::Weak) => wifi_icons.weak.clone(),
+            WifiState::Connected(WifiConnectedState::Good) => wifi_icons.good.clone(),
+            WifiState::Connected(WifiConnectedState::Strong) => wifi_icons.strong.clone(),
+        };
+        if wifi_icon != widgets.wifi_icon {
+            if let Some(icon) = wifi_icon.as_ref() {
+                widgets.wifi_image.set_file(Some(icon));
+            }
+            widgets.wifi_icon = wifi_icon;
+        }
+
+        let battery_icons = &self.settings.battery.icon;
+        let battery_icon = match self.battery_state {
+            BatteryState::Level0 => battery_icons.level_0.clone(),
+            BatteryState::Level10 => battery_icons.level_10.clone(),
+            BatteryState::Level20 => battery_icons.level_20.clone(),
+            BatteryState::Level30 => battery_icons.level_30.clone(),
+            BatteryState::Level40 => battery_icons.level_40.clone(),
+            BatteryState::Level50 => battery_icons.level_50.clone(),
+            BatteryState::Level60 => battery_icons.level_60.clone(),
+            BatteryState::Level70 => battery_icons.level_70.clone(),
+            BatteryState::Level80 => battery_icons.level_80.clone(),
+            BatteryState::Level90 => battery_icons.level_90.clone(),
+            BatteryState::Level100 => battery_icons.level_100.clone(),
+        };
+        if battery_icon != widgets.battery_icon {
+            if let Some(icon) = battery_icon.as_ref() {
+                widgets.battery_image.set_file(Some(icon));
+            }
+            widgets.battery_icon = battery_icon;
+        }
+    }
 
     fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
-        info!("home shutdown called")
+        info!("home shutdown called");
+        if self.is_flashlight_on {
+            flashlight::set_flashlight(&self.settings.flashlight.device_path, false);
+        }
+    }
+}
+
+/// The format is checked when the settings are read, see `settings::is_valid_clock_format`
+fn format_now(format_string: &str) -> String {
+    format!("{}", Local::now().format(format_string))
+}
+
+/// Applies the theme background (color and optional image) to the home page
+fn apply_background(background: &Background) {
+    let [r, g, b] = background.color;
+    let mut css = format!("background-color: rgb({}, {}, {});", r, g, b);
+
+    if let Some(image) = background.image.as_ref() {
+        let image_uri = gio::File::for_path(image).uri();
+        css.push_str(&format!("background-image: url(\"{}\");", image_uri));
+        let fill_css = match background.fill.clone().unwrap_or_default() {
+            BackgroundFillType::Centered => {
+                "background-size: auto; background-position: center; background-repeat: no-repeat;"
+            }
+            BackgroundFillType::Stretch => {
+                "background-size: 100% 100%; background-repeat: no-repeat;"
+            }
+            BackgroundFillType::Cover => {
+                "background-size: cover; background-position: center; background-repeat: no-repeat;"
+            }
+        };
+        css.push_str(fill_css);
     }
+
+    let provider = CssProvider::new();
+    provider.load_from_data(&format!(".home-background {{ {} }}", css));
+    gtk::style_context_add_provider_for_display(
+        &Display::default().expect("Could not connect to a display."),
+        &provider,
+        STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
+
+/// The state while the service answers, else the default state. The error is
+/// only logged once the service stops answering, not on every refresh
+fn state_or_default<T: Default>(
+    result: Result<T, LockScreenError>,
+    is_unavailable: &mut bool,
+) -> T {
+    match result {
+        Ok(state) => {
+            if *is_unavailable {
+                info!("device status is available again");
+            }
+            *is_unavailable = false;
+            state
+        }
+        Err(e) => {
+            if !*is_unavailable {
+                LockScreenError::new(e.code, e.message);
+            }
+            *is_unavailable = true;
+            T::default()
+        }
+    }
+}
+
+async fn get_wifi_data() -> Result<WifiState, LockScreenError> {
+    let url = std::env::var("GRPC_SERVER_URL").unwrap_or_default();
+    let mut network_manager_client = match NetworkManagerClient::new(url).await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(LockScreenError {
+                code: LockScreenErrorCodes::InitNetworkManagerClient,
+                message: format!("unable to create network manager client error - {}", e),
+            });
+        }
+    };
+
+    let wifi_status = match network_manager_client.get_wireless_network_status().await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(LockScreenError {
+                code: LockScreenErrorCodes::GetWifiStatusError,
+                message: format!("unable to get wireless network status error - {}", e),
+            });
+        }
+    };
+
+    let wifi_state = match (wifi_status.wifi_on, wifi_status.current_network) {
+        (false, _) => WifiState::Off,
+        (true, None) => WifiState::On,
+        (true, Some(current_network)) => {
+            if current_network.signal <= -80 {
+                WifiState::Connected(WifiConnectedState::Low)
+            } else if current_network.signal <= -60 {
+                WifiState::Connected(WifiConnectedState::Weak)
+            } else if current_network.signal <= -40 {
+                WifiState::Connected(WifiConnectedState::Good)
+            } else {
+                WifiState::Connected(WifiConnectedState::Strong)
+            }
+        }
+    };
+
+    Ok(wifi_state)
+}
+
+async fn get_battery_data() -> Result<BatteryState, LockScreenError> {
+    let url = std::env::var("GRPC_SERVER_URL").unwrap_or_default();
+    let mut battery_manager_client = match BatteryManagerClient::new(url).await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(LockScreenError {
+                code: LockScreenErrorCodes::InitBatteryManagerClient,
+                message: format!("unable to create battery manager client error - {}", e),
+            });
+        }
+    };
+
+    let battery_status = match battery_manager_client.get_battery_status().await {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(LockScreenError {
+                code: LockScreenErrorCodes::GetBatteryStatusError,
+                message: format!("unable to get battery status error - {}", e),
+            });
+        }
+    };
+
+    let battery_capacity = battery_status.capacity.parse::<u8>().unwrap_or(0);
+
+    let battery_state = match battery_capacity {
+        0..=9 => BatteryState::Level0,
+        10..=19 => BatteryState::Level10,
+        20..=29 => BatteryState::Level20,
+        30..=39 => BatteryState::Level30,
+        40..=49 => BatteryState::Level40,
+        50..=59 => BatteryState::Level50,
+        60..=69 => BatteryState::Level60,
+        70..=79 => BatteryState::Level70,
+        80..=89 => BatteryState::Level80,
+        90..=99 => BatteryState::Level90,
+        _ => BatteryState::Level100,
+    };
+
+    Ok(battery_state)
 }
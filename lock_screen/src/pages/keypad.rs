@@ -0,0 +1,141 @@
+use gtk::gdk;
+use rand::seq::SliceRandom;
+use relm4::{factory::FactoryVecDeque, gtk};
+
+use crate::{
+    settings::{Modules, PasswordConfigsModule},
+    widgets::{
+        password_key::{PasswordKey, PasswordKeySettings},
+        password_text::{Message as PasswordTextMessage, PasswordText, PasswordTextSettings},
+    },
+};
+
+/// Keys of the PIN keypad, shared by the pages that take a PIN
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeypadKey {
+    Digit(String),
+    BackSpace,
+    Submit,
+    Home,
+}
+
+impl KeypadKey {
+    /// Key for the title of a clicked keypad key
+    pub fn from_title(key: String, modules: &Modules) -> Self {
+        if key == modules.home.title {
+            KeypadKey::Home
+        } else if key == modules.back_space.title {
+            KeypadKey::BackSpace
+        } else if key == modules.submit.title {
+            KeypadKey::Submit
+        } else {
+            KeypadKey::Digit(key)
+        }
+    }
+
+    /// Key for a hardware keyboard key, hardware keyboards
+    /// type the pin the same way the keypad does
+    pub fn from_keyval(keyval: gdk::Key, modules: &Modules) -> Option<Self> {
+        match keyval {
+            gdk::Key::BackSpace => Some(KeypadKey::BackSpace),
+            gdk::Key::Escape => Some(KeypadKey::Home),
+            gdk::Key::Return | gdk::Key::KP_Enter => Some(KeypadKey::Submit),
+            _ => keyval
+                .to_unicode()
+                .map(|c| c.to_string())
+                .filter(|key| modules.password_configs.keys_allowed.contains(key))
+                .map(KeypadKey::Digit),
+        }
+    }
+}
+
+pub fn password_keys_container() -> gtk::FlowBox {
+    gtk::FlowBox::builder()
+        .valign(gtk::Align::Start)
+        .max_children_per_line(30)
+        .min_children_per_line(4)
+        .selection_mode(gtk::SelectionMode::None)
+        .row_spacing(5)
+        .column_spacing(5)
+        .build()
+}
+
+pub fn password_texts_container() -> gtk::Box {
+    gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .css_classes(["password-text-box"])
+        .halign(gtk::Align::Center)
+        .hexpand(true)
+        .spacing(16)
+        .build()
+}
+
+/// Fills the keypad from the layout grid, shuffling the pin keys
+/// among their own positions when `scramble` is set
+pub fn populate_password_keys(
+    password_keys: &mut FactoryVecDeque<PasswordKey>,
+    modules: &Modules,
+    grid: &[String],
+    scramble: bool,
+) {
+    let keys_allowed = &modules.password_configs.keys_allowed;
+
+    let mut pin_keys: Vec<String> = grid
+        .iter()
+        .filter(|key| keys_allowed.contains(key))
+        .cloned()
+        .collect();
+    if scramble {
+        pin_keys.shuffle(&mut rand::thread_rng());
+    }
+    let mut pin_keys = pin_keys.into_iter();
+
+    let mut password_keys = password_keys.guard();
+    password_keys.clear();
+    grid.iter().cloned().for_each(|key| {
+        let key = match keys_allowed.contains(&key) {
+            true => pin_keys.next().unwrap_or(key),
+            false => key,
+        };
+
+        let mut icon: Option<String> = None;
+
+        if key == modules.home.title {
+            icon = modules.home.icon.default.to_owned()
+        } else if key == modules.back_space.title {
+            icon = modules.back_space.icon.default.to_owned()
+        } else if key == modules.submit.title {
+            icon = modules.submit.icon.default.to_owned()
+        }
+
+        password_keys.push_back(PasswordKeySettings { key, icon });
+    });
+}
+
+/// Fixed length pins show one dot per digit of the pin,
+/// variable length pins show one dot per digit entered
+pub fn refresh_password_texts(
+    password_texts: &mut FactoryVecDeque<PasswordText>,
+    password_configs: &PasswordConfigsModule,
+    entered: usize,
+) {
+    if password_configs.variable_length {
+        let mut password_texts = password_texts.guard();
+        password_texts.clear();
+        for _ in 0..entered {
+            password_texts.push_back(PasswordTextSettings { is_filled: true });
+        }
+        return;
+    }
+
+    if password_texts.len() != password_configs.password_length {
+        let mut password_texts = password_texts.guard();
+        password_texts.clear();
+        for _ in 0..password_configs.password_length {
+            password_texts.push_back(PasswordTextSettings { is_filled: false });
+        }
+    }
+    for index in 0..password_configs.password_length {
+        password_texts.send(index, PasswordTextMessage::SetFilled(index < entered));
+    }
+}
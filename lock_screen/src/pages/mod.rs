@@ -1,3 +1,9 @@
+pub mod animations;
+pub mod attempts;
+pub mod gestures;
 pub mod home;
+pub mod keypad;
 pub mod password_authentication;
+pub mod pattern_authentication;
 pub mod pin_authentication;
+pub mod pin_enrollment;
@@ -1,22 +1,33 @@
-use std::{env, os::unix::net::UnixStream};
+use std::{
+    env,
+    os::unix::net::UnixStream,
+    time::{Duration, Instant},
+};
 
 use anyhow::bail;
 use anyhow::Result;
 use custom_widgets::icon_input::IconPosition;
 use gtk::{
     gdk, gio,
-    glib::clone,
+    glib::{self, clone},
     prelude::{BoxExt, ButtonExt, EditableExt, EntryExt, WidgetExt},
 };
 use relm4::Controller;
 use relm4::{
     factory::FactoryVecDeque, gtk, Component, ComponentController, ComponentParts, ComponentSender,
-    SimpleComponent,
+    RelmWidgetExt, SimpleComponent,
 };
 
+use super::gestures::{self, SwipeDirection};
 use crate::{
     errors::{LockScreenError, LockScreenErrorCodes},
-    settings::{LayoutSettings, Modules},
+    sessions::{Session, SessionCommand},
+    settings::{AuthMessagesModule, LayoutSettings, Modules},
+    users::User,
+    widgets::user_tile::{
+        InputMessage as UserTileInputMessage, Message as UserTileMessage, UserTile,
+        UserTileSettings,
+    },
 };
 use custom_widgets::{
     icon_button::{
@@ -44,14 +55,24 @@ use tracing::info;
 pub struct Settings {
     pub modules: Modules,
     pub layout: LayoutSettings,
+    pub users: Vec<User>,
+    pub last_user: Option<String>,
+    pub allow_manual_username: bool,
+    pub login_timeout: u64, // Seconds, 0 waits as long as the login manager takes
+    pub start_session: bool,    // A login only succeeds once a session is started
+    pub sessions: Vec<Session>, // Empty unless a session is started after login
+    pub default_session: Option<String>,
+    pub session_env: Vec<String>,
+    pub swipe_to_home: bool,
 }
 
 //Model
 pub struct PasswordAuthentication {
     settings: Settings,
-    username: String,
-    password: String,
-    login_status: Option<LoginResult>,
+    form: LoginForm,
+    user_tiles: FactoryVecDeque<UserTile>,
+    username_input_sender: relm4::Sender<IconInputInputMessage>,
+    password_input_sender: relm4::Sender<IconInputPasswordInputMessage>,
 }
 
 //Widgets
@@ -67,15 +88,49 @@ pub struct PasswordAuthenticationWidgets {
 #[derive(Debug)]
 pub enum Message {
     UsernameChange(String),
+    UserSelected(String),
     PasswordChange(String),
     Submit,
     BackPressed,
+    LoggedIn(String),
+    LoginFinished(LoginResult),
+    UsernameActivated,
+    SessionSelected(usize),
+    Focus,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoginFailureReason {
+    BadCredentials,
+    AccountLocked,
+    PamError,
+    DaemonUnreachable,
+    Timeout,
+    NoSession,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum LoginResult {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoginResult {
     Success,
-    Failure,
+    Failure(LoginFailureReason),
+}
+
+impl LoginResult {
+    /// Message shown for the result, taken from the settings
+    fn message(&self, messages: &AuthMessagesModule) -> String {
+        let message = match self {
+            LoginResult::Success => &messages.success,
+            LoginResult::Failure(LoginFailureReason::BadCredentials) => &messages.bad_credentials,
+            LoginResult::Failure(LoginFailureReason::AccountLocked) => &messages.account_locked,
+            LoginResult::Failure(LoginFailureReason::PamError) => &messages.pam_error,
+            LoginResult::Failure(LoginFailureReason::DaemonUnreachable) => {
+                &messages.daemon_unreachable
+            }
+            LoginResult::Failure(LoginFailureReason::Timeout) => &messages.timeout,
+            LoginResult::Failure(LoginFailureReason::NoSession) => &messages.no_session,
+        };
+        message.to_owned()
+    }
 }
 
 impl SimpleComponent for PasswordAuthentication {
@@ -109,6 +164,42 @@ impl SimpleComponent for PasswordAuthentication {
 
         let login_res_label = gtk::Label::builder().build();
 
+        // preselect the last logged-in user, else the first one listed
+        let selected_username = match init
+            .last_user
+            .as_ref()
+            .filter(|last_user| init.users.iter().any(|user| &user.username == *last_user))
+        {
+            Some(last_user) => last_user.to_owned(),
+            None => init
+                .users
+                .first()
+                .map(|user| user.username.to_owned())
+                .unwrap_or_default(),
+        };
+
+        let mut user_tiles: FactoryVecDeque<UserTile> = FactoryVecDeque::builder(
+            gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .css_classes(["user-picker"])
+                .halign(gtk::Align::Center)
+                .spacing(16)
+                .build(),
+        )
+        .launch()
+        .forward(sender.input_sender(), |msg| match msg {
+            UserTileMessage::UserClicked(username) => Message::UserSelected(username),
+        });
+
+        for user in init.users.iter() {
+            user_tiles.guard().push_back(UserTileSettings {
+                username: user.username.to_owned(),
+                display_name: user.display_name.to_owned(),
+                avatar: user.avatar.to_owned(),
+                is_selected: user.username == selected_username,
+            });
+        }
+
         let username_input = IconInput::builder()
             .launch(IconInputSettings {
                 clear_icon: None,
@@ -118,6 +209,7 @@ impl SimpleComponent for PasswordAuthentication {
             })
             .forward(sender.input_sender(), |msg| match msg {
                 IconInputOutputMessage::InputChange(text) => Message::UsernameChange(text),
+                IconInputOutputMessage::Activate => Message::UsernameActivated,
             });
 
         let password_input = IconInputPassword::builder()
@@ -129,6 +221,7 @@ impl SimpleComponent for PasswordAuthentication {
             })
             .forward(sender.input_sender(), |msg| match msg {
                 IconInputPasswordOutputMessage::InputChange(text) => Message::PasswordChange(text),
+                IconInputPasswordOutputMessage::Activate => Message::Submit,
             });
 
         let back_button = IconButton::builder()
@@ -171,19 +264,69 @@ impl SimpleComponent for PasswordAuthentication {
 
         // form_box.append(test_input.widget());
         form_box.append(&login_label);
+        form_box.append(user_tiles.widget());
         form_box.append(username_input.widget());
+        user_tiles.widget().set_visible(!init.users.is_empty());
+        username_input
+            .widget()
+            .set_visible(init.users.is_empty() || init.allow_manual_username);
         form_box.append(password_input.widget());
+
+        let session_names: Vec<&str> = init
+            .sessions
+            .iter()
+            .map(|session| session.name.as_str())
+            .collect();
+        let selected_session = init
+            .default_session
+            .as_ref()
+            .and_then(|name| init.sessions.iter().position(|session| &session.name == name))
+            .unwrap_or(0);
+        let session_dropdown = gtk::DropDown::from_strings(&session_names);
+        session_dropdown.add_css_class("session-chooser");
+        session_dropdown.set_selected(selected_session as u32);
+        session_dropdown.set_visible(!init.sessions.is_empty());
+        session_dropdown.connect_selected_notify(clone!(@strong sender => move |dropdown| {
+            sender.input(Message::SessionSelected(dropdown.selected() as usize));
+        }));
+        form_box.append(&session_dropdown);
         form_box.append(&login_res_label);
 
         root.append(&form_box);
         root.append(&footer);
         root.set_focus_child(Option::from(&login_res_label));
 
+        // Enter is handled by the inputs themselves, Escape leaves the page
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(clone!(@strong sender => move |_, keyval, _, _| {
+            match keyval {
+                gdk::Key::Escape => {
+                    sender.input(Message::BackPressed);
+                    glib::Propagation::Stop
+                }
+                _ => glib::Propagation::Proceed,
+            }
+        }));
+        root.add_controller(key_controller);
+
+        if init.swipe_to_home {
+            gestures::add_swipe_controller(
+                root,
+                SwipeDirection::Down,
+                clone!(@strong sender => move || sender.input(Message::BackPressed)),
+            );
+        }
+
         let model = PasswordAuthentication {
             settings: init,
-            username: "".to_string(),
-            password: "".to_string(),
-            login_status: None,
+            form: LoginForm {
+                username: selected_username,
+                selected_session,
+                ..LoginForm::default()
+            },
+            user_tiles,
+            username_input_sender: username_input.sender().clone(),
+            password_input_sender: password_input.sender().clone(),
         };
 
         let widgets = PasswordAuthenticationWidgets {
@@ -198,65 +341,241 @@ impl SimpleComponent for PasswordAuthentication {
     }
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
-        info!("Update message is {:?}", message);
+        // the changed text is the password
+        if !matches!(message, Message::PasswordChange(_)) {
+            info!("Update message is {:?}", message);
+        }
         match message {
             Message::UsernameChange(value) => {
-                self.username = value;
+                if !value.is_empty() {
+                    self.username_input_sender
+                        .emit(IconInputInputMessage::SetError(false));
+                }
+                self.form.username = value;
+            }
+            Message::UserSelected(username) => {
+                for (index, user) in self.settings.users.iter().enumerate() {
+                    self.user_tiles.send(
+                        index,
+                        UserTileInputMessage::SetSelected(user.username == username),
+                    );
+                }
+                self.form.username = username;
             }
             Message::PasswordChange(value) => {
-                self.password = value;
+                if !value.is_empty() {
+                    self.password_input_sender
+                        .emit(IconInputPasswordInputMessage::SetError(false));
+                }
+                self.form.password = value;
             }
             Message::Submit => {
-                let login_res = login(self.username.clone(), self.password.clone());
-                self.login_status = match login_res {
-                    Ok(r) => Some(r),
-                    Err(_) => Some(LoginResult::Failure),
-                };
+                if let Some(request) = self.form.submit(&self.settings) {
+                    // the conversation blocks on greetd, it is kept off the gtk thread
+                    relm4::spawn_blocking(clone!(@strong sender => move || {
+                        sender.input(Message::LoginFinished(request.run()));
+                    }));
+                }
             }
+            Message::LoginFinished(login_status) => match self.form.finish(login_status) {
+                Some(username) => {
+                    sender.output(Message::LoggedIn(username));
+                }
+                None => {
+                    self.password_input_sender
+                        .emit(IconInputPasswordInputMessage::Clear);
+                    let is_credentials_error =
+                        login_status == LoginResult::Failure(LoginFailureReason::BadCredentials);
+                    self.username_input_sender
+                        .emit(IconInputInputMessage::SetError(is_credentials_error));
+                    self.password_input_sender
+                        .emit(IconInputPasswordInputMessage::SetError(is_credentials_error));
+                    self.password_input_sender
+                        .emit(IconInputPasswordInputMessage::Focus);
+                }
+            },
             Message::BackPressed => {
                 sender.output_sender().send(Message::BackPressed);
             }
+            Message::UsernameActivated => {
+                self.password_input_sender
+                    .emit(IconInputPasswordInputMessage::Focus);
+            }
+            Message::SessionSelected(index) => {
+                self.form.selected_session = index;
+            }
+            Message::Focus => {
+                // start typing the password when a user is already picked
+                match self.form.username.is_empty() {
+                    true => self.username_input_sender.emit(IconInputInputMessage::Focus),
+                    false => self
+                        .password_input_sender
+                        .emit(IconInputPasswordInputMessage::Focus),
+                };
+            }
+            Message::LoggedIn(_) => (),
         }
     }
 
     fn update_view(&self, widgets: &mut Self::Widgets, sender: ComponentSender<Self>) {
-        match self.login_status {
-            Some(login_status) => match login_status {
-                LoginResult::Success => {
-                    widgets.login_res_label.set_label("Login successfull");
-                }
-                LoginResult::Failure => {
-                    widgets.login_res_label.set_label("Login failed");
-                }
-            },
+        match self.form.login_status {
+            Some(login_status) => {
+                let message = login_status.message(&self.settings.modules.auth_messages);
+                widgets.login_res_label.set_label(&message);
+                widgets
+                    .login_res_label
+                    .set_class_active("login-error-label", login_status != LoginResult::Success);
+            }
             None => (),
         }
     }
 }
 
-fn login(username: String, password: String) -> Result<LoginResult> {
-    let login_manager_url = match env::var("LOGIN_MANAGER_URL") {
-        Ok(v) => v,
+/// # Login Form
+///
+/// What is entered on the page and where its login stands,
+/// kept apart from the widgets
+#[derive(Debug, Default)]
+struct LoginForm {
+    username: String,
+    password: String,
+    selected_session: usize,
+    is_logging_in: bool,
+    login_status: Option<LoginResult>,
+}
+
+impl LoginForm {
+    /// Starts a login for the entered user, nothing is started while
+    /// one is running or when the session to start is missing
+    fn submit(&mut self, settings: &Settings) -> Option<LoginRequest> {
+        // a conversation is already running for the last submit
+        if self.is_logging_in {
+            return None;
+        }
+
+        let session = settings
+            .sessions
+            .get(self.selected_session)
+            .map(|session| SessionCommand {
+                cmd: session.command(),
+                env: settings.session_env.clone(),
+            });
+        // unlocking without starting the session would leave nothing running
+        if settings.start_session && session.is_none() {
+            LockScreenError::new(
+                LockScreenErrorCodes::NoSessionError,
+                "sessions are enabled but none is available to start".to_string(),
+            );
+            self.login_status = Some(LoginResult::Failure(LoginFailureReason::NoSession));
+            return None;
+        }
+
+        self.is_logging_in = true;
+        Some(LoginRequest {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            session,
+            timeout: login_timeout(settings.login_timeout),
+        })
+    }
+
+    /// Ends the running login, giving the user that logged in
+    fn finish(&mut self, login_status: LoginResult) -> Option<String> {
+        self.is_logging_in = false;
+        self.login_status = Some(login_status);
+        match login_status {
+            LoginResult::Success => Some(self.username.clone()),
+            LoginResult::Failure(_) => {
+                self.password.clear();
+                None
+            }
+        }
+    }
+}
+
+/// A submitted login, run away from the gtk thread
+struct LoginRequest {
+    username: String,
+    password: String,
+    session: Option<SessionCommand>,
+    timeout: Option<Duration>,
+}
+
+impl LoginRequest {
+    /// Logs in on the login manager found at `LOGIN_MANAGER_URL`
+    fn run(self) -> LoginResult {
+        let login_res = login_manager_url().and_then(|login_manager_url| {
+            login(
+                &login_manager_url,
+                self.username,
+                self.password,
+                self.session,
+                self.timeout,
+            )
+        });
+        match login_res {
+            Ok(r) => r,
+            Err(e) => LoginResult::Failure(error_failure_reason(&e)),
+        }
+    }
+}
+
+fn login_manager_url() -> Result<String> {
+    match env::var("LOGIN_MANAGER_URL") {
+        Ok(v) => Ok(v),
         Err(_) => {
             bail!(LockScreenError::new(
                 LockScreenErrorCodes::FindLoginManagerUrlError,
-                format!("unable to read LOGIN_MANAGER_URL in env"),
+                "unable to read LOGIN_MANAGER_URL in env".to_string(),
             ));
         }
-    };
+    }
+}
 
-    let mut stream = match UnixStream::connect(login_manager_url) {
-        Ok(v) => v,
+/// # Login
+///
+/// Runs a greetd conversation for the user on the login
+/// manager listening at the path, see `converse`
+pub fn login(
+    login_manager_url: &str,
+    username: String,
+    password: String,
+    session: Option<SessionCommand>,
+    timeout: Option<Duration>,
+) -> Result<LoginResult> {
+    let mut stream = connect_login_manager(login_manager_url)?;
+    converse(&mut stream, username, password, session, timeout)
+}
+
+fn connect_login_manager(login_manager_url: &str) -> Result<UnixStream> {
+    match UnixStream::connect(login_manager_url) {
+        Ok(v) => Ok(v),
         Err(e) => {
             bail!(LockScreenError::new(
                 LockScreenErrorCodes::LoginManagerStreamConnectError,
                 format!("unable to connect to login manager stream error: {}", e),
             ));
         }
-    };
+    }
+}
+
+/// # Converse
+///
+/// Answers every secret prompt of greetd with the password until
+/// greetd accepts or rejects the user, when a session is given it
+/// is started once the user is accepted
+fn converse(
+    stream: &mut UnixStream,
+    username: String,
+    password: String,
+    session: Option<SessionCommand>,
+    timeout: Option<Duration>,
+) -> Result<LoginResult> {
+    let _ = stream.set_read_timeout(timeout);
+    let _ = stream.set_write_timeout(timeout);
 
     let username_message = GreetdRequest::CreateSession { username };
-    let write_username_message_result = username_message.write_to(&mut stream);
+    let write_username_message_result = username_message.write_to(stream);
     match write_username_message_result {
         Ok(_) => (),
         Err(e) => {
@@ -267,84 +586,545 @@ fn login(username: String, password: String) -> Result<LoginResult> {
         }
     }
 
-    let read_enter_password_message = GreetdResponse::read_from(&mut stream);
-    match read_enter_password_message {
-        Ok(r) => r,
-        Err(e) => {
+    loop {
+        let read_started_at = Instant::now();
+        let auth_response = match GreetdResponse::read_from(stream) {
+            Ok(r) => r,
+            Err(e) => {
+                // the codec drops the io error kind, so timeouts are told apart by the wait
+                let is_timed_out = timeout.is_some_and(|timeout| read_started_at.elapsed() >= timeout);
+                let code = match is_timed_out {
+                    true => LockScreenErrorCodes::LoginTimeoutError,
+                    false => LockScreenErrorCodes::StreamReadAuthResponseError,
+                };
+                bail!(LockScreenError::new(
+                    code,
+                    format!("unable to read auth response from stream error: {}", e),
+                ));
+            }
+        };
+
+        let auth_message_response = match auth_response {
+            GreetdResponse::Success => {
+                return match session {
+                    Some(session) => start_session(stream, session),
+                    None => {
+                        // only checking the password, greetd would otherwise keep
+                        // the authenticated session around for the next client
+                        cancel_session(stream);
+                        Ok(LoginResult::Success)
+                    }
+                };
+            }
+            GreetdResponse::Error {
+                error_type,
+                description,
+            } => {
+                info!(task = "login", "login failed {:?} {}", error_type, description);
+                cancel_session(stream);
+                return Ok(LoginResult::Failure(failure_reason(
+                    error_type,
+                    &description,
+                )));
+            }
+            GreetdResponse::AuthMessage {
+                auth_message_type,
+                auth_message,
+            } => match auth_message_type {
+                AuthMessageType::Secret => Some(password.clone()),
+                AuthMessageType::Info | AuthMessageType::Error => {
+                    info!(task = "login", "login manager says {}", auth_message);
+                    None
+                }
+                AuthMessageType::Visible => {
+                    // there is nothing to answer visible prompts with
+                    info!(task = "login", "unsupported prompt {}", auth_message);
+                    cancel_session(stream);
+                    return Ok(LoginResult::Failure(LoginFailureReason::PamError));
+                }
+            },
+        };
+
+        let code = match auth_message_response.is_some() {
+            true => LockScreenErrorCodes::StreamWritePasswordError,
+            false => LockScreenErrorCodes::StreamWriteAuthResponseError,
+        };
+        let response_message = GreetdRequest::PostAuthMessageResponse {
+            response: auth_message_response,
+        };
+        if let Err(e) = response_message.write_to(stream) {
             bail!(LockScreenError::new(
-                LockScreenErrorCodes::StreamReadEnterPasswordError,
-                format!("unable to read next message to enter password error: {}", e),
+                code,
+                format!("unable to write auth response in stream error: {}", e),
             ));
         }
-    };
+    }
+}
 
-    let password_message = GreetdRequest::PostAuthMessageResponse {
-        response: Option::from(password),
+/// Asks greetd to start the session, which it does as soon as the greeter exits
+fn start_session(stream: &mut UnixStream, session: SessionCommand) -> Result<LoginResult> {
+    info!(task = "login", "starting session {:?}", session.cmd);
+    let start_session_message = GreetdRequest::StartSession {
+        cmd: session.cmd,
+        env: session.env,
     };
-    let write_password_message_result = password_message.write_to(&mut stream);
-    match write_password_message_result {
-        Ok(_) => (),
+    if let Err(e) = start_session_message.write_to(stream) {
+        bail!(LockScreenError::new(
+            LockScreenErrorCodes::StartSessionError,
+            format!("unable to write start session message in stream error: {}", e),
+        ));
+    }
+
+    let start_session_response = match GreetdResponse::read_from(stream) {
+        Ok(r) => r,
         Err(e) => {
             bail!(LockScreenError::new(
-                LockScreenErrorCodes::StreamWritePasswordError,
-                format!("unable to write password message in stream error: {}", e),
+                LockScreenErrorCodes::StartSessionError,
+                format!("unable to read start session response error: {}", e),
             ));
         }
     };
 
-    let read_enter_captcha_message = GreetdResponse::read_from(&mut stream);
-    match read_enter_captcha_message {
-        Ok(_) => (),
-        Err(e) => {
-            bail!(LockScreenError::new(
-                LockScreenErrorCodes::StreamReadCaptchaError,
-                format!(
-                    "unable to read enter captcha message from stream error: {}",
-                    e
-                ),
-            ));
+    match start_session_response {
+        GreetdResponse::Success => Ok(LoginResult::Success),
+        response => {
+            LockScreenError::new(
+                LockScreenErrorCodes::StartSessionError,
+                format!("login manager refused to start the session - {:?}", response),
+            );
+            cancel_session(stream);
+            Ok(LoginResult::Failure(LoginFailureReason::PamError))
+        }
+    }
+}
+
+/// The socket rejects a zero timeout, 0 seconds waits without one
+fn login_timeout(seconds: u64) -> Option<Duration> {
+    match seconds {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+fn cancel_session(stream: &mut UnixStream) {
+    if GreetdRequest::CancelSession.write_to(stream).is_ok() {
+        let _ = GreetdResponse::read_from(stream);
+    }
+}
+
+/// Maps a greetd error to the reason shown to the user, PAM only
+/// reports lockouts in the description so it is matched on text
+fn failure_reason(error_type: ErrorType, description: &str) -> LoginFailureReason {
+    match error_type {
+        ErrorType::AuthError => {
+            let description = description.to_lowercase();
+            let is_locked = ["locked", "maximum number", "expired"]
+                .iter()
+                .any(|pattern| description.contains(pattern));
+            match is_locked {
+                true => LoginFailureReason::AccountLocked,
+                false => LoginFailureReason::BadCredentials,
+            }
         }
+        ErrorType::Error => LoginFailureReason::PamError,
     }
+}
 
-    let captcha_message = GreetdRequest::PostAuthMessageResponse {
-        response: Option::from("9".to_string()),
+/// Reason for errors raised before greetd had a say
+fn error_failure_reason(error: &anyhow::Error) -> LoginFailureReason {
+    match error.downcast_ref::<LockScreenError>().map(|e| e.code) {
+        Some(LockScreenErrorCodes::LoginTimeoutError) => LoginFailureReason::Timeout,
+        _ => LoginFailureReason::DaemonUnreachable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_greetd::{
+            auth_error, error, info_message, secret_prompt, visible_prompt, MockGreetd, Step,
+        },
+        settings::Modules,
     };
-    let write_captcha_message_result = captcha_message.write_to(&mut stream);
-    match write_captcha_message_result {
-        Ok(_) => (),
-        Err(e) => {
-            bail!(LockScreenError::new(
-                LockScreenErrorCodes::StreamWriteCaptchaError,
-                format!("unable to write captcha message into stream error: {}", e),
-            ));
+
+    const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+    fn run_login(
+        script: Vec<Step>,
+        session: Option<SessionCommand>,
+    ) -> (Result<LoginResult>, Vec<GreetdRequest>) {
+        let (mock_greetd, mut stream) = MockGreetd::start(script);
+        let result = converse(
+            &mut stream,
+            "user".to_string(),
+            "password".to_string(),
+            session,
+            TIMEOUT,
+        );
+        drop(stream);
+        (result, mock_greetd.requests())
+    }
+
+    fn auth_responses(requests: &[GreetdRequest]) -> Vec<Option<String>> {
+        requests
+            .iter()
+            .filter_map(|request| match request {
+                GreetdRequest::PostAuthMessageResponse { response } => Some(response.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn login_succeeds_with_password() {
+        let (result, requests) = run_login(
+            vec![
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(GreetdResponse::Success),
+            ],
+            None,
+        );
+
+        assert_eq!(result.unwrap(), LoginResult::Success);
+        assert!(matches!(
+            &requests[0],
+            GreetdRequest::CreateSession { username } if username == "user"
+        ));
+        assert_eq!(
+            auth_responses(&requests),
+            vec![Some("password".to_string())]
+        );
+        assert!(matches!(
+            requests.last(),
+            Some(GreetdRequest::CancelSession)
+        ));
+    }
+
+    #[test]
+    fn wrong_password_is_bad_credentials() {
+        let (result, requests) = run_login(
+            vec![
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(auth_error("Authentication failure")),
+            ],
+            None,
+        );
+
+        assert_eq!(
+            result.unwrap(),
+            LoginResult::Failure(LoginFailureReason::BadCredentials)
+        );
+        assert!(matches!(
+            requests.last(),
+            Some(GreetdRequest::CancelSession)
+        ));
+    }
+
+    #[test]
+    fn locked_account_is_reported() {
+        let (result, _) = run_login(
+            vec![
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(auth_error("The account is locked due to 5 failed logins")),
+            ],
+            None,
+        );
+
+        assert_eq!(
+            result.unwrap(),
+            LoginResult::Failure(LoginFailureReason::AccountLocked)
+        );
+    }
+
+    #[test]
+    fn login_manager_error_is_pam_error() {
+        let (result, _) = run_login(vec![Step::Reply(error("pam_start failed"))], None);
+
+        assert_eq!(
+            result.unwrap(),
+            LoginResult::Failure(LoginFailureReason::PamError)
+        );
+    }
+
+    #[test]
+    fn multi_prompt_conversation_answers_every_prompt() {
+        let (result, requests) = run_login(
+            vec![
+                Step::Reply(info_message("Welcome")),
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(secret_prompt("Keyring password:")),
+                Step::Reply(GreetdResponse::Success),
+            ],
+            None,
+        );
+
+        assert_eq!(result.unwrap(), LoginResult::Success);
+        assert_eq!(
+            auth_responses(&requests),
+            vec![
+                None,
+                Some("password".to_string()),
+                Some("password".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn visible_prompt_cancels_the_session() {
+        let (result, requests) = run_login(vec![Step::Reply(visible_prompt("Captcha:"))], None);
+
+        assert_eq!(
+            result.unwrap(),
+            LoginResult::Failure(LoginFailureReason::PamError)
+        );
+        assert!(matches!(
+            requests.last(),
+            Some(GreetdRequest::CancelSession)
+        ));
+    }
+
+    #[test]
+    fn disconnect_mid_conversation_is_daemon_unreachable() {
+        let (result, _) = run_login(
+            vec![Step::Reply(secret_prompt("Password:")), Step::Disconnect],
+            None,
+        );
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error_failure_reason(&error),
+            LoginFailureReason::DaemonUnreachable
+        );
+    }
+
+    #[test]
+    fn silent_login_manager_times_out() {
+        let (mock_greetd, mut stream) = MockGreetd::start(vec![Step::Stall]);
+        let result = converse(
+            &mut stream,
+            "user".to_string(),
+            "password".to_string(),
+            None,
+            Some(Duration::from_millis(100)),
+        );
+        drop(stream);
+        mock_greetd.requests();
+
+        let error = result.unwrap_err();
+        assert_eq!(error_failure_reason(&error), LoginFailureReason::Timeout);
+    }
+
+    #[test]
+    fn session_is_started_after_login() {
+        let session = SessionCommand {
+            cmd: vec!["sway".to_string()],
+            env: vec!["XDG_SESSION_TYPE=wayland".to_string()],
+        };
+        let (result, requests) = run_login(
+            vec![
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(GreetdResponse::Success),
+                Step::Reply(GreetdResponse::Success),
+            ],
+            Some(session.clone()),
+        );
+
+        assert_eq!(result.unwrap(), LoginResult::Success);
+        assert!(matches!(
+            requests.last(),
+            Some(GreetdRequest::StartSession { cmd, env }) if *cmd == session.cmd && *env == session.env
+        ));
+    }
+
+    #[test]
+    fn refused_session_is_pam_error() {
+        let session = SessionCommand {
+            cmd: vec!["sway".to_string()],
+            env: vec![],
+        };
+        let (result, _) = run_login(
+            vec![
+                Step::Reply(GreetdResponse::Success),
+                Step::Reply(error("unable to start session")),
+            ],
+            Some(session),
+        );
+
+        assert_eq!(
+            result.unwrap(),
+            LoginResult::Failure(LoginFailureReason::PamError)
+        );
+    }
+
+    #[test]
+    fn missing_login_manager_is_daemon_unreachable() {
+        let error = connect_login_manager("/nonexistent/greetd.sock").unwrap_err();
+
+        assert_eq!(
+            error_failure_reason(&error),
+            LoginFailureReason::DaemonUnreachable
+        );
+    }
+
+    #[test]
+    fn login_connects_to_the_given_login_manager() {
+        let socket_path = env::temp_dir().join(format!("mock-greetd-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let mock_greetd = MockGreetd::listen(
+            &socket_path,
+            vec![
+                Step::Reply(secret_prompt("Password:")),
+                Step::Reply(GreetdResponse::Success),
+            ],
+        );
+
+        let result = login(
+            socket_path.to_str().unwrap(),
+            "user".to_string(),
+            "password".to_string(),
+            None,
+            TIMEOUT,
+        );
+        mock_greetd.requests();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(result.unwrap(), LoginResult::Success);
+    }
+
+    #[test]
+    fn zero_login_timeout_waits_without_one() {
+        assert_eq!(login_timeout(0), None);
+        assert_eq!(login_timeout(10), Some(Duration::from_secs(10)));
+    }
+
+    fn page_settings(sessions: Vec<Session>, start_session: bool) -> Settings {
+        Settings {
+            modules: Modules::default(),
+            layout: LayoutSettings::default(),
+            users: vec![],
+            last_user: None,
+            allow_manual_username: true,
+            login_timeout: 5,
+            start_session,
+            sessions,
+            default_session: None,
+            session_env: vec![],
+            swipe_to_home: false,
         }
     }
 
-    let read_auth_response = GreetdResponse::read_from(&mut stream);
-    let auth_response = match read_auth_response {
-        Ok(r) => r,
-        Err(e) => {
-            bail!(LockScreenError::new(
-                LockScreenErrorCodes::StreamReadAuthResponseError,
-                format!(
-                    "unable to read enter auth response from stream error: {}",
-                    e
-                ),
-            ));
+    fn filled_form() -> LoginForm {
+        LoginForm {
+            username: "user".to_string(),
+            password: "password".to_string(),
+            ..LoginForm::default()
         }
-    };
+    }
 
-    let login_res = match auth_response {
-        GreetdResponse::Success => LoginResult::Success,
-        GreetdResponse::Error {
-            error_type,
-            description,
-        } => LoginResult::Failure,
-        GreetdResponse::AuthMessage {
-            auth_message_type,
-            auth_message,
-        } => LoginResult::Failure,
-    };
+    #[test]
+    fn submit_starts_a_single_login() {
+        let settings = page_settings(vec![], false);
+        let mut form = filled_form();
+
+        let request = form.submit(&settings).unwrap();
+        assert_eq!(request.username, "user");
+        assert_eq!(request.password, "password");
+        assert_eq!(request.session, None);
+        assert!(form.is_logging_in);
+
+        // submitting again while the login runs does nothing
+        assert!(form.submit(&settings).is_none());
+    }
+
+    #[test]
+    fn submit_starts_the_selected_session() {
+        let sessions = vec![
+            Session {
+                name: "GNOME".to_string(),
+                exec: "gnome-session".to_string(),
+            },
+            Session {
+                name: "Sway".to_string(),
+                exec: "sway".to_string(),
+            },
+        ];
+        let settings = page_settings(sessions, true);
+        let mut form = LoginForm {
+            selected_session: 1,
+            ..filled_form()
+        };
+
+        let request = form.submit(&settings).unwrap();
+        assert_eq!(request.session.unwrap().cmd, vec!["sway".to_string()]);
+    }
+
+    #[test]
+    fn missing_session_fails_without_logging_in() {
+        let settings = page_settings(vec![], true);
+        let mut form = filled_form();
 
-    Ok(login_res)
+        assert!(form.submit(&settings).is_none());
+        assert!(!form.is_logging_in);
+        assert_eq!(
+            form.login_status,
+            Some(LoginResult::Failure(LoginFailureReason::NoSession))
+        );
+    }
+
+    #[test]
+    fn successful_login_gives_the_user_to_unlock() {
+        let settings = page_settings(vec![], false);
+        let mut form = filled_form();
+        let request = form.submit(&settings).unwrap();
+
+        let (mock_greetd, mut stream) = MockGreetd::start(vec![
+            Step::Reply(secret_prompt("Password:")),
+            Step::Reply(GreetdResponse::Success),
+        ]);
+        let login_status = converse(
+            &mut stream,
+            request.username,
+            request.password,
+            request.session,
+            request.timeout,
+        )
+        .unwrap();
+        drop(stream);
+        mock_greetd.requests();
+
+        assert_eq!(form.finish(login_status), Some("user".to_string()));
+        assert!(!form.is_logging_in);
+        assert_eq!(form.login_status, Some(LoginResult::Success));
+    }
+
+    #[test]
+    fn failed_login_clears_the_password_for_a_retry() {
+        let settings = page_settings(vec![], false);
+        let mut form = filled_form();
+        form.submit(&settings).unwrap();
+
+        let failure = LoginResult::Failure(LoginFailureReason::BadCredentials);
+        assert_eq!(form.finish(failure), None);
+        assert_eq!(form.password, "");
+        assert_eq!(form.login_status, Some(failure));
+
+        form.password = "another password".to_string();
+        assert!(form.submit(&settings).is_some());
+    }
+
+    #[test]
+    fn every_result_has_its_own_message() {
+        let messages = Modules::default().auth_messages;
+
+        assert_eq!(LoginResult::Success.message(&messages), messages.success);
+        assert_eq!(
+            LoginResult::Failure(LoginFailureReason::Timeout).message(&messages),
+            messages.timeout
+        );
+        assert_eq!(
+            LoginResult::Failure(LoginFailureReason::AccountLocked).message(&messages),
+            messages.account_locked
+        );
+    }
 }
@@ -0,0 +1,538 @@
+use std::{cell::RefCell, f64::consts::PI, rc::Rc};
+
+use gtk::{
+    cairo, gdk,
+    glib::{self, clone},
+    prelude::{BoxExt, DrawingAreaExtManual, GestureDragExt, GestureExt, WidgetExt},
+};
+use relm4::{
+    gtk, Component, ComponentController, ComponentParts, ComponentSender, Controller,
+    SimpleComponent,
+};
+
+use super::{
+    animations,
+    attempts::{Attempts, Lockout, Settings as AttemptsSettings},
+    gestures::{self, SwipeDirection},
+};
+use crate::{
+    credentials, haptics,
+    settings::{AuthMethod, Modules, PatternConfigsModule},
+};
+use custom_widgets::icon_button::{
+    IconButton, IconButtonCss, InitSettings as IconButtonStetings,
+    OutputMessage as IconButtonOutputMessage,
+};
+use tracing::info;
+
+/// Dots per row and column of the grid
+const GRID_SIZE: usize = 3;
+
+/// Share of a cell around a dot that picks it up while dragging
+const NODE_HIT_RADIUS: f64 = 0.3;
+
+/// Share of a cell drawn as the dot
+const NODE_RADIUS: f64 = 0.08;
+
+const NODE_COLOR: (f64, f64, f64) = (1.0, 1.0, 1.0);
+const PATH_COLOR: (f64, f64, f64) = (0.196, 0.514, 0.910);
+const ERROR_COLOR: (f64, f64, f64) = (0.910, 0.196, 0.196);
+
+//Init Settings
+pub struct Settings {
+    pub modules: Modules,
+    pub credentials_path: String,
+    pub attempts: AttemptsSettings, // AttemptsExceeded is sent once they run out
+    pub swipe_to_home: bool,
+}
+
+/// What the drawing area renders, shared with the draw function
+/// and the drag gesture
+#[derive(Debug, Default)]
+struct PatternState {
+    nodes: Vec<usize>,
+    pointer: Option<(f64, f64)>,
+    is_failed: bool,
+}
+
+//Model
+pub struct PatternAuthentication {
+    settings: Settings,
+    state: Rc<RefCell<PatternState>>,
+    status: PatternStatus,
+    attempts: Attempts,
+    root: gtk::Box,
+    drawing_area: gtk::DrawingArea,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PatternStatus {
+    Idle,
+    TooShort,
+    Wrong,
+    NotSet,
+    Delayed(u64), // Seconds left before the next attempt
+    LockedOut,
+}
+
+//Widgets
+pub struct PatternAuthenticationWidgets {
+    pattern_status_label: gtk::Label,
+    home_btn: Controller<IconButton>,
+}
+
+//Messages
+#[derive(Debug)]
+pub enum Message {
+    PatternStarted,
+    NodeAdded,
+    PatternEntered(Vec<usize>),
+    HomeIconPressed,
+    AttemptsExceeded,
+    Authenticated,
+    Focus,
+}
+
+impl SimpleComponent for PatternAuthentication {
+    type Init = Settings;
+    type Input = Message;
+    type Output = Message;
+    type Root = gtk::Box;
+    type Widgets = PatternAuthenticationWidgets;
+
+    fn init_root() -> Self::Root {
+        gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["pattern-auth-container"])
+            .focusable(true)
+            .build()
+    }
+
+    fn init(
+        init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let state = Rc::new(RefCell::new(PatternState::default()));
+        let pattern_configs = init.modules.pattern_configs.clone();
+
+        let pattern_status_label = gtk::Label::builder()
+            .css_classes(["pattern-status-label"])
+            .build();
+
+        let drawing_area = gtk::DrawingArea::builder()
+            .css_classes(["pattern-grid"])
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+
+        drawing_area.set_draw_func(
+            clone!(@strong state, @strong pattern_configs => move |_, cr, width, height| {
+                draw_pattern(cr, width as f64, height as f64, &state.borrow(), &pattern_configs);
+            }),
+        );
+
+        // dots are picked up by dragging across them, each one only once
+        let drag_gesture = gtk::GestureDrag::new();
+        drag_gesture.connect_drag_begin(
+            clone!(@strong state, @strong sender, @weak drawing_area => move |gesture, x, y| {
+                // drawing on the grid is never a swipe back to home
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                {
+                    let mut state = state.borrow_mut();
+                    *state = PatternState::default();
+                    state.pointer = Some((x, y));
+                }
+                sender.input(Message::PatternStarted);
+                if add_node_at(&state, &drawing_area, x, y) {
+                    sender.input(Message::NodeAdded);
+                }
+                drawing_area.queue_draw();
+            }),
+        );
+        drag_gesture.connect_drag_update(
+            clone!(@strong state, @strong sender, @weak drawing_area => move |gesture, offset_x, offset_y| {
+                let Some((start_x, start_y)) = gesture.start_point() else {
+                    return;
+                };
+                let (x, y) = (start_x + offset_x, start_y + offset_y);
+                state.borrow_mut().pointer = Some((x, y));
+                if add_node_at(&state, &drawing_area, x, y) {
+                    sender.input(Message::NodeAdded);
+                }
+                drawing_area.queue_draw();
+            }),
+        );
+        drag_gesture.connect_drag_end(
+            clone!(@strong state, @strong sender, @weak drawing_area => move |_, _, _| {
+                let nodes = {
+                    let mut state = state.borrow_mut();
+                    state.pointer = None;
+                    state.nodes.clone()
+                };
+                drawing_area.queue_draw();
+                if !nodes.is_empty() {
+                    sender.input(Message::PatternEntered(nodes));
+                }
+            }),
+        );
+        drawing_area.add_controller(drag_gesture);
+
+        let home_btn = IconButton::builder()
+            .launch(IconButtonStetings {
+                icon: init.modules.home.icon.default.to_owned(),
+                toggle_icon: None,
+                css: IconButtonCss::default(),
+            })
+            .forward(sender.input_sender(), |msg| match msg {
+                IconButtonOutputMessage::Clicked => Message::HomeIconPressed,
+            });
+        home_btn.widget().set_halign(gtk::Align::Center);
+
+        root.append(&pattern_status_label);
+        root.append(&drawing_area);
+        root.append(home_btn.widget());
+
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(clone!(@strong sender => move |_, keyval, _, _| {
+            match keyval {
+                gdk::Key::Escape => {
+                    sender.input(Message::HomeIconPressed);
+                    glib::Propagation::Stop
+                }
+                _ => glib::Propagation::Proceed,
+            }
+        }));
+        root.add_controller(key_controller);
+
+        if init.swipe_to_home {
+            gestures::add_swipe_controller(
+                root,
+                SwipeDirection::Down,
+                clone!(@strong sender => move || sender.input(Message::HomeIconPressed)),
+            );
+        }
+
+        let attempts = Attempts::new(init.attempts.clone());
+        let model = PatternAuthentication {
+            settings: init,
+            state,
+            status: PatternStatus::Idle,
+            attempts,
+            root: root.clone(),
+            drawing_area,
+        };
+
+        let widgets = PatternAuthenticationWidgets {
+            pattern_status_label,
+            home_btn,
+        };
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        // the entered nodes are the pattern
+        if !matches!(message, Message::PatternEntered(_)) {
+            info!("Update message is {:?}", message);
+        }
+        match message {
+            Message::PatternStarted => {
+                self.status = PatternStatus::Idle;
+            }
+            Message::NodeAdded => {
+                self.node_feedback();
+            }
+            Message::PatternEntered(nodes) => {
+                self.submit(nodes, &sender);
+                self.drawing_area.queue_draw();
+            }
+            Message::HomeIconPressed => {
+                self.clear();
+                sender.output(Message::HomeIconPressed);
+            }
+            Message::Focus => {
+                self.status = match self.attempts.lockout() {
+                    Lockout::None => PatternStatus::Idle,
+                    Lockout::Delayed(seconds) => PatternStatus::Delayed(seconds),
+                    Lockout::UntilPassword => PatternStatus::LockedOut,
+                };
+                self.root.grab_focus();
+            }
+            Message::AttemptsExceeded | Message::Authenticated => (),
+        }
+    }
+
+    fn update_view(&self, widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+        let min_length = self.settings.modules.pattern_configs.min_length;
+        let label = match self.status {
+            PatternStatus::Idle => "Draw your pattern".to_string(),
+            PatternStatus::TooShort => format!("Connect at least {} dots", min_length),
+            PatternStatus::Wrong => "Wrong pattern, Please try again!".to_string(),
+            PatternStatus::NotSet => "No pattern is set up".to_string(),
+            PatternStatus::Delayed(seconds) => {
+                format!("Too many wrong patterns, try again in {}s", seconds)
+            }
+            PatternStatus::LockedOut => {
+                "Too many wrong patterns, unlock with your password".to_string()
+            }
+        };
+        widgets.pattern_status_label.set_label(&label);
+    }
+}
+
+impl PatternAuthentication {
+    fn submit(&mut self, nodes: Vec<usize>, sender: &ComponentSender<Self>) {
+        // too short patterns are not counted as attempts
+        if nodes.len() < self.settings.modules.pattern_configs.min_length {
+            self.status = PatternStatus::TooShort;
+            self.state.borrow_mut().is_failed = true;
+            return;
+        }
+
+        // patterns drawn while locked out are not even checked
+        match self.attempts.lockout() {
+            Lockout::None => (),
+            Lockout::Delayed(seconds) => {
+                self.clear();
+                self.status = PatternStatus::Delayed(seconds);
+                return;
+            }
+            Lockout::UntilPassword => {
+                self.clear();
+                self.status = PatternStatus::LockedOut;
+                sender.output(Message::AttemptsExceeded);
+                return;
+            }
+        }
+
+        let stored_pattern = credentials::read_credentials_yml(&self.settings.credentials_path)
+            .ok()
+            .and_then(|credentials| credentials.pattern);
+        let Some(stored_pattern) = stored_pattern else {
+            self.status = PatternStatus::NotSet;
+            self.state.borrow_mut().is_failed = true;
+            return;
+        };
+
+        if credentials::verify_secret(&pattern_to_string(&nodes), &stored_pattern) {
+            self.attempts.reset();
+            self.clear();
+            sender.output(Message::Authenticated);
+            return;
+        }
+
+        self.status = PatternStatus::Wrong;
+        self.state.borrow_mut().is_failed = true;
+        animations::shake(&self.drawing_area);
+        match self.attempts.failed(AuthMethod::Pattern) {
+            Lockout::None => (),
+            Lockout::Delayed(seconds) => {
+                self.clear();
+                self.status = PatternStatus::Delayed(seconds);
+            }
+            Lockout::UntilPassword => {
+                info!(task = "auth user", "pattern attempts exceeded");
+                self.clear();
+                self.status = PatternStatus::LockedOut;
+                sender.output(Message::AttemptsExceeded);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self.state.borrow_mut() = PatternState::default();
+        self.status = PatternStatus::Idle;
+        self.drawing_area.queue_draw();
+    }
+
+    fn node_feedback(&self) {
+        let haptics_settings = &self.settings.modules.haptics;
+        if haptics_settings.enabled {
+            haptics::vibrate(&haptics_settings.device_path, haptics_settings.duration);
+        }
+    }
+}
+
+/// Stored form of a pattern, the dot indexes in the order connected
+pub fn pattern_to_string(nodes: &[usize]) -> String {
+    nodes.iter().map(|node| node.to_string()).collect()
+}
+
+/// Pattern back from its stored form, `None` unless every dot is on
+/// the grid, connected once and the pattern could have been drawn
+pub fn pattern_from_string(pattern: &str) -> Option<Vec<usize>> {
+    let mut nodes: Vec<usize> = vec![];
+    for digit in pattern.chars() {
+        let node = digit.to_digit(10)? as usize;
+        if node >= GRID_SIZE * GRID_SIZE || nodes.contains(&node) {
+            return None;
+        }
+        // drawing across a dot not connected yet picks it up
+        let skipped = nodes.last().and_then(|last| node_between(*last, node));
+        if skipped.is_some_and(|skipped| !nodes.contains(&skipped)) {
+            return None;
+        }
+        nodes.push(node);
+    }
+    Some(nodes)
+}
+
+/// Picks up the dot under the pointer, with any dot it jumped over on
+/// the way, true when the pattern got longer
+fn add_node_at(
+    state: &Rc<RefCell<PatternState>>,
+    drawing_area: &gtk::DrawingArea,
+    x: f64,
+    y: f64,
+) -> bool {
+    let width = drawing_area.width() as f64;
+    let height = drawing_area.height() as f64;
+    let Some(node) = node_at(width, height, x, y) else {
+        return false;
+    };
+
+    let mut state = state.borrow_mut();
+    if state.nodes.contains(&node) {
+        return false;
+    }
+    if let Some(skipped) = state
+        .nodes
+        .last()
+        .and_then(|last| node_between(*last, node))
+    {
+        if !state.nodes.contains(&skipped) {
+            state.nodes.push(skipped);
+        }
+    }
+    state.nodes.push(node);
+    true
+}
+
+/// Size of a grid cell and the offset centering the grid in the area
+fn grid_geometry(width: f64, height: f64) -> (f64, f64, f64) {
+    let size = width.min(height);
+    let cell = size / GRID_SIZE as f64;
+    (cell, (width - size) / 2.0, (height - size) / 2.0)
+}
+
+fn node_center(width: f64, height: f64, node: usize) -> (f64, f64) {
+    let (cell, offset_x, offset_y) = grid_geometry(width, height);
+    let column = (node % GRID_SIZE) as f64;
+    let row = (node / GRID_SIZE) as f64;
+    (
+        offset_x + cell * (column + 0.5),
+        offset_y + cell * (row + 0.5),
+    )
+}
+
+/// Dot within reach of the point, if any
+fn node_at(width: f64, height: f64, x: f64, y: f64) -> Option<usize> {
+    let (cell, _, _) = grid_geometry(width, height);
+    (0..GRID_SIZE * GRID_SIZE).find(|node| {
+        let (center_x, center_y) = node_center(width, height, *node);
+        (x - center_x).hypot(y - center_y) <= cell * NODE_HIT_RADIUS
+    })
+}
+
+/// Dot lying exactly halfway between two dots, if any
+fn node_between(from: usize, to: usize) -> Option<usize> {
+    let (from_row, from_column) = (from / GRID_SIZE, from % GRID_SIZE);
+    let (to_row, to_column) = (to / GRID_SIZE, to % GRID_SIZE);
+    if (from_row + to_row) % 2 != 0 || (from_column + to_column) % 2 != 0 {
+        return None;
+    }
+    let node = (from_row + to_row) / 2 * GRID_SIZE + (from_column + to_column) / 2;
+    match node == from || node == to {
+        true => None,
+        false => Some(node),
+    }
+}
+
+fn draw_pattern(
+    cr: &cairo::Context,
+    width: f64,
+    height: f64,
+    state: &PatternState,
+    pattern_configs: &PatternConfigsModule,
+) {
+    let (cell, _, _) = grid_geometry(width, height);
+    let (red, green, blue) = match state.is_failed {
+        true => ERROR_COLOR,
+        false => PATH_COLOR,
+    };
+
+    if pattern_configs.show_path && !state.nodes.is_empty() {
+        cr.set_source_rgb(red, green, blue);
+        cr.set_line_width(cell * 0.04);
+        cr.set_line_cap(cairo::LineCap::Round);
+        cr.set_line_join(cairo::LineJoin::Round);
+        for (index, node) in state.nodes.iter().enumerate() {
+            let (x, y) = node_center(width, height, *node);
+            match index {
+                0 => cr.move_to(x, y),
+                _ => cr.line_to(x, y),
+            }
+        }
+        if let Some((x, y)) = state.pointer {
+            cr.line_to(x, y);
+        }
+        let _ = cr.stroke();
+    }
+
+    for node in 0..GRID_SIZE * GRID_SIZE {
+        let (x, y) = node_center(width, height, node);
+        let is_selected = pattern_configs.show_path && state.nodes.contains(&node);
+        match is_selected {
+            true => cr.set_source_rgb(red, green, blue),
+            false => cr.set_source_rgb(NODE_COLOR.0, NODE_COLOR.1, NODE_COLOR.2),
+        }
+        cr.arc(x, y, cell * NODE_RADIUS, 0.0, 2.0 * PI);
+        let _ = cr.fill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_map_to_the_dot_in_reach() {
+        // 300x300 area, cells of 100 with dots at 50, 150, 250
+        assert_eq!(node_at(300.0, 300.0, 50.0, 50.0), Some(0));
+        assert_eq!(node_at(300.0, 300.0, 160.0, 140.0), Some(4));
+        assert_eq!(node_at(300.0, 300.0, 250.0, 250.0), Some(8));
+        assert_eq!(node_at(300.0, 300.0, 100.0, 100.0), None);
+    }
+
+    #[test]
+    fn grid_is_centered_in_wide_areas() {
+        assert_eq!(node_center(500.0, 300.0, 0), (150.0, 50.0));
+        assert_eq!(node_at(500.0, 300.0, 50.0, 50.0), None);
+    }
+
+    #[test]
+    fn jumped_over_dots_are_found() {
+        assert_eq!(node_between(0, 2), Some(1));
+        assert_eq!(node_between(0, 8), Some(4));
+        assert_eq!(node_between(6, 0), Some(3));
+        assert_eq!(node_between(0, 1), None);
+        assert_eq!(node_between(0, 5), None);
+    }
+
+    #[test]
+    fn patterns_are_stored_as_dot_indexes() {
+        assert_eq!(pattern_to_string(&[0, 3, 4, 8]), "0348");
+        assert_eq!(pattern_from_string("0348"), Some(vec![0, 3, 4, 8]));
+    }
+
+    #[test]
+    fn undrawable_patterns_are_rejected() {
+        assert_eq!(pattern_from_string("039"), None);
+        assert_eq!(pattern_from_string("0340"), None);
+        assert_eq!(pattern_from_string("03a4"), None);
+        // 0 to 2 crosses 1, unless it is already connected
+        assert_eq!(pattern_from_string("0258"), None);
+        assert_eq!(pattern_from_string("1025"), Some(vec![1, 0, 2, 5]));
+    }
+}
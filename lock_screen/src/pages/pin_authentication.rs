@@ -1,14 +1,24 @@
-use gtk::{glib::clone, prelude::BoxExt};
+use gtk::{
+    glib::{self, clone},
+    prelude::{BoxExt, WidgetExt},
+};
 use relm4::{
     factory::FactoryVecDeque, gtk, Component, ComponentController, ComponentParts, ComponentSender,
     SimpleComponent,
 };
 
+use super::{
+    animations,
+    attempts::{Attempts, Lockout, Settings as AttemptsSettings},
+    gestures::{self, SwipeDirection},
+    keypad::{self, KeypadKey},
+};
 use crate::{
-    settings::{LayoutSettings, Modules},
+    credentials, haptics,
+    settings::{AuthMethod, LayoutSettings, Modules},
     widgets::{
-        password_key::{Message as PasswordKeyMessage, PasswordKey, PasswordKeySettings},
-        password_text::{Message as PasswordTextMessage, PasswordText, PasswordTextSettings},
+        password_key::{Message as PasswordKeyMessage, PasswordKey},
+        password_text::PasswordText,
     },
 };
 use tracing::info;
@@ -17,6 +27,9 @@ use tracing::info;
 pub struct Settings {
     pub modules: Modules,
     pub layout: LayoutSettings,
+    pub credentials_path: String,
+    pub attempts: AttemptsSettings, // AttemptsExceeded is sent once they run out
+    pub swipe_to_home: bool,
 }
 
 //Model
@@ -24,13 +37,19 @@ pub struct PinAuthentication {
     settings: Settings,
     password: String,
     is_authentication_failed: bool,
+    is_pin_not_set: bool,
+    attempts: Attempts,
+    lockout: Lockout,
     password_texts: FactoryVecDeque<PasswordText>,
     password_keys: FactoryVecDeque<PasswordKey>,
+    root: gtk::Box,
+    authenticator_status: Option<String>,
 }
 
 //Widgets
 pub struct PinAuthenticationWidgets {
     password_invalid_label: gtk::Label,
+    authenticator_status_label: gtk::Label,
 }
 
 //Messages
@@ -38,7 +57,23 @@ pub struct PinAuthenticationWidgets {
 pub enum Message {
     PasswordKeyPressed(String),
     BackSpacePressed,
+    SubmitPressed,
     HomeIconPressed,
+    AttemptsExceeded,
+    Authenticated,
+    AuthenticatorStatus(String),
+    Focus,
+}
+
+impl From<KeypadKey> for Message {
+    fn from(key: KeypadKey) -> Self {
+        match key {
+            KeypadKey::Digit(key) => Message::PasswordKeyPressed(key),
+            KeypadKey::BackSpace => Message::BackSpacePressed,
+            KeypadKey::Submit => Message::SubmitPressed,
+            KeypadKey::Home => Message::HomeIconPressed,
+        }
+    }
 }
 
 impl SimpleComponent for PinAuthentication {
@@ -52,6 +87,7 @@ impl SimpleComponent for PinAuthentication {
         gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .css_classes(["pin-auth-container"])
+            .focusable(true)
             .build()
     }
 
@@ -61,135 +97,233 @@ impl SimpleComponent for PinAuthentication {
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let modules = init.modules.clone();
-        let layout = init.layout.clone();
-
-        let mut password_keys: FactoryVecDeque<PasswordKey> = FactoryVecDeque::builder(
-            gtk::FlowBox::builder()
-                .valign(gtk::Align::Start)
-                .max_children_per_line(30)
-                .min_children_per_line(4)
-                .selection_mode(gtk::SelectionMode::None)
-                .row_spacing(5)
-                .column_spacing(5)
-                .build(),
-        )
-        .launch()
-        .forward(
-            sender.input_sender(),
-            clone!(@strong modules => move|msg| match msg {
-                PasswordKeyMessage::WidgetClicked(key) => {
-                    if key == modules.home.title {
-                        Message::HomeIconPressed
-                    } else if key == modules.back_space.title {
-                        return Message::BackSpacePressed
-                    } else {
-                        return Message::PasswordKeyPressed(key);
-                    }
-                }
-            }),
-        );
 
-        layout.grid.into_iter().for_each(|key| {
-            let mut icon: Option<String> = None;
+        let password_keys: FactoryVecDeque<PasswordKey> =
+            FactoryVecDeque::builder(keypad::password_keys_container())
+                .launch()
+                .forward(
+                    sender.input_sender(),
+                    clone!(@strong modules => move|msg| match msg {
+                        PasswordKeyMessage::WidgetClicked(key) => {
+                            KeypadKey::from_title(key, &modules).into()
+                        }
+                    }),
+                );
 
-            if key == modules.home.title {
-                icon = modules.home.icon.default.to_owned()
-            } else if key == modules.back_space.title {
-                icon = modules.back_space.icon.default.to_owned()
-            }
-
-            // info!("key: {} icon: {:?}", key, icon);
-
-            password_keys
-                .guard()
-                .push_back(PasswordKeySettings { key, icon });
-        });
-
-        let mut password_texts = FactoryVecDeque::builder(
-            gtk::Box::builder()
-                .orientation(gtk::Orientation::Horizontal)
-                .css_classes(["password-text-box"])
-                .halign(gtk::Align::Center)
-                .hexpand(true)
-                .spacing(16)
-                .build(),
-        )
-        .launch()
-        .detach();
-
-        for i in 0..modules.password_configs.password_length {
-            password_texts
-                .guard()
-                .insert(i, PasswordTextSettings { is_filled: false });
-        }
+        let password_texts = FactoryVecDeque::builder(keypad::password_texts_container())
+            .launch()
+            .detach();
 
         let password_invalid_label = gtk::Label::builder()
             .css_classes(["password-invalid-label"])
             .build();
 
+        // prompts of the authenticators running next to the pin
+        let authenticator_status_label = gtk::Label::builder()
+            .css_classes(["authenticator-status-label"])
+            .visible(false)
+            .build();
+
+        root.append(&authenticator_status_label);
         root.append(password_texts.widget());
         root.append(&password_invalid_label);
         root.append(password_keys.widget());
 
-        let model = PinAuthentication {
+        // hardware keyboards type the pin the same way the keypad does
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(clone!(@strong sender, @strong modules => move |_, keyval, _, _| {
+            match KeypadKey::from_keyval(keyval, &modules) {
+                Some(key) => {
+                    sender.input(key.into());
+                    glib::Propagation::Stop
+                }
+                None => glib::Propagation::Proceed,
+            }
+        }));
+        root.add_controller(key_controller);
+
+        if init.swipe_to_home {
+            gestures::add_swipe_controller(
+                root,
+                SwipeDirection::Down,
+                clone!(@strong sender => move || sender.input(Message::HomeIconPressed)),
+            );
+        }
+
+        let attempts = Attempts::new(init.attempts.clone());
+        let mut model = PinAuthentication {
             settings: init,
             password: "".to_string(),
             is_authentication_failed: false,
+            is_pin_not_set: false,
+            attempts,
+            lockout: Lockout::None,
             password_texts,
             password_keys,
+            root: root.clone(),
+            authenticator_status: None,
         };
+        model.populate_password_keys();
+        model.refresh_password_texts();
 
         let widgets = PinAuthenticationWidgets {
             password_invalid_label,
+            authenticator_status_label,
         };
 
         ComponentParts { model, widgets }
     }
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
-        info!("Update message is {:?}", message);
+        // key presses would spell out the pin
+        if !matches!(message, Message::PasswordKeyPressed(_)) {
+            info!("Update message is {:?}", message);
+        }
+        let password_configs = self.settings.modules.password_configs.clone();
         match message {
             Message::PasswordKeyPressed(password_key) => {
-                info!(task = "key presed", "key Pressed is {:?}", password_key);
-                self.is_authentication_failed = false;
-                self.password = [self.password.to_owned(), password_key].join("");
-                self.password_texts
-                    .send(self.password.len() - 1, PasswordTextMessage::ToggleFilled);
-                let is_password_wrong = *"1234" != self.password;
-                let is_password_length_reached =
-                    self.password.len() == self.settings.modules.password_configs.password_length;
-                if is_password_length_reached {
-                    info!(task = "auth user", "password entered is {}", self.password);
+                if self.password.len() >= password_configs.password_length {
+                    return;
                 }
+                self.key_feedback();
+                self.is_authentication_failed = false;
+                self.is_pin_not_set = false;
+                self.password.push_str(&password_key);
+                self.refresh_password_texts();
 
-                if is_password_length_reached && is_password_wrong {
-                    self.password = String::from("");
-                    self.is_authentication_failed = true;
-                    for num in 0..self.settings.modules.password_configs.password_length {
-                        self.password_texts
-                            .send(num, PasswordTextMessage::ToggleFilled);
-                    }
+                // fixed length pins are submitted as soon as the length is reached
+                let is_password_length_reached =
+                    self.password.len() == password_configs.password_length;
+                if !password_configs.variable_length && is_password_length_reached {
+                    self.submit(&sender);
                 }
             }
             Message::BackSpacePressed => {
-                if self.password.len() <= 0 {
+                if self.password.is_empty() {
                     return;
                 }
+                self.key_feedback();
                 self.password.pop();
-                self.password_texts
-                    .send(self.password.len(), PasswordTextMessage::ToggleFilled);
+                self.refresh_password_texts();
+            }
+            Message::SubmitPressed => {
+                if self.password.len() < password_configs.min_length {
+                    return;
+                }
+                self.key_feedback();
+                self.submit(&sender);
             }
             Message::HomeIconPressed => {
                 sender.output(Message::HomeIconPressed);
             }
+            Message::Focus => {
+                self.lockout = self.attempts.lockout();
+                self.root.grab_focus();
+            }
+            Message::AuthenticatorStatus(status) => {
+                self.authenticator_status = Some(status);
+            }
+            Message::AttemptsExceeded | Message::Authenticated => (),
         }
     }
 
     fn update_view(&self, widgets: &mut Self::Widgets, sender: ComponentSender<Self>) {
-        if self.is_authentication_failed.to_owned() {
-            widgets
+        match (
+            self.lockout,
+            self.is_authentication_failed,
+            self.is_pin_not_set,
+        ) {
+            (Lockout::Delayed(seconds), _, _) => widgets
                 .password_invalid_label
-                .set_label("Invalid pin, Please try again!")
+                .set_label(&format!("Too many wrong pins, try again in {}s", seconds)),
+            (Lockout::UntilPassword, _, _) => widgets
+                .password_invalid_label
+                .set_label("Too many wrong pins, unlock with your password"),
+            (_, true, _) => widgets
+                .password_invalid_label
+                .set_label("Invalid pin, Please try again!"),
+            (_, false, true) => widgets.password_invalid_label.set_label("No pin is set up"),
+            (_, false, false) => widgets.password_invalid_label.set_label(""),
+        };
+        if let Some(status) = self.authenticator_status.as_ref() {
+            widgets.authenticator_status_label.set_label(status);
+            widgets.authenticator_status_label.set_visible(true);
+        }
+    }
+}
+
+impl PinAuthentication {
+    fn submit(&mut self, sender: &ComponentSender<Self>) {
+        // pins entered while locked out are not even checked
+        self.lockout = self.attempts.lockout();
+        if self.lockout != Lockout::None {
+            self.password = String::from("");
+            self.refresh_password_texts();
+            if self.lockout == Lockout::UntilPassword {
+                sender.output(Message::AttemptsExceeded);
+            }
+            return;
+        }
+
+        let stored_pin = credentials::read_credentials_yml(&self.settings.credentials_path)
+            .ok()
+            .and_then(|credentials| credentials.pin);
+        let is_password_wrong = match stored_pin.as_ref() {
+            Some(stored_pin) => !credentials::verify_secret(&self.password, stored_pin),
+            None => true,
         };
+
+        self.password = String::from("");
+        self.refresh_password_texts();
+
+        // a fresh layout after every attempt keeps smudges meaningless
+        if self.settings.modules.password_configs.scramble_keypad {
+            self.populate_password_keys();
+        }
+
+        if !is_password_wrong {
+            self.attempts.reset();
+            sender.output(Message::Authenticated);
+            return;
+        }
+
+        // without a stored pin there is nothing to count attempts against
+        if stored_pin.is_none() {
+            self.is_pin_not_set = true;
+            return;
+        }
+
+        self.is_authentication_failed = true;
+        animations::shake(self.password_texts.widget());
+        self.lockout = self.attempts.failed(AuthMethod::Pin);
+        if self.lockout == Lockout::UntilPassword {
+            info!(task = "auth user", "pin attempts exceeded");
+            self.is_authentication_failed = false;
+            sender.output(Message::AttemptsExceeded);
+        }
+    }
+
+    fn populate_password_keys(&mut self) {
+        keypad::populate_password_keys(
+            &mut self.password_keys,
+            &self.settings.modules,
+            &self.settings.layout.grid,
+            self.settings.modules.password_configs.scramble_keypad,
+        );
+    }
+
+    fn refresh_password_texts(&mut self) {
+        keypad::refresh_password_texts(
+            &mut self.password_texts,
+            &self.settings.modules.password_configs,
+            self.password.len(),
+        );
+    }
+
+    fn key_feedback(&self) {
+        let haptics_settings = &self.settings.modules.haptics;
+        if haptics_settings.enabled {
+            haptics::vibrate(&haptics_settings.device_path, haptics_settings.duration);
+        }
     }
 }
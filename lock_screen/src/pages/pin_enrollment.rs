@@ -0,0 +1,352 @@
+use gtk::{
+    glib::{self, clone},
+    prelude::{BoxExt, WidgetExt},
+};
+use relm4::{factory::FactoryVecDeque, gtk, ComponentParts, ComponentSender, SimpleComponent};
+
+use super::{
+    animations,
+    keypad::{self, KeypadKey},
+};
+use crate::{
+    credentials, haptics,
+    settings::{LayoutSettings, Modules},
+    widgets::{
+        password_key::{Message as PasswordKeyMessage, PasswordKey},
+        password_text::PasswordText,
+    },
+};
+use tracing::info;
+
+//Init Settings
+pub struct Settings {
+    pub modules: Modules,
+    pub layout: LayoutSettings,
+    pub credentials_path: String,
+    pub max_attempts: u32, // Wrong current PINs in a row before changes wait for an unlock, 0 disables
+}
+
+/// Steps of the enrollment, the current PIN is only
+/// asked for when one is set
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnrollmentStep {
+    Current,
+    New,
+    Confirm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnrollmentError {
+    WrongPin,
+    Mismatch,
+    SaveFailed,
+    LockedOut,
+}
+
+//Model
+pub struct PinEnrollment {
+    settings: Settings,
+    step: EnrollmentStep,
+    password: String,
+    new_password: Option<String>,
+    stored_pin: Option<String>,
+    error: Option<EnrollmentError>,
+    failed_attempts: u32, // Kept in the credentials file, see `record_failed_attempts`
+    password_texts: FactoryVecDeque<PasswordText>,
+    password_keys: FactoryVecDeque<PasswordKey>,
+    root: gtk::Box,
+}
+
+//Widgets
+pub struct PinEnrollmentWidgets {
+    step_label: gtk::Label,
+    error_label: gtk::Label,
+}
+
+//Messages
+#[derive(Debug)]
+pub enum Message {
+    Start,
+    PasswordKeyPressed(String),
+    BackSpacePressed,
+    SubmitPressed,
+    CancelPressed,
+    Focus,
+    Cancelled,
+    Completed,
+}
+
+impl From<KeypadKey> for Message {
+    fn from(key: KeypadKey) -> Self {
+        match key {
+            KeypadKey::Digit(key) => Message::PasswordKeyPressed(key),
+            KeypadKey::BackSpace => Message::BackSpacePressed,
+            KeypadKey::Submit => Message::SubmitPressed,
+            KeypadKey::Home => Message::CancelPressed,
+        }
+    }
+}
+
+impl SimpleComponent for PinEnrollment {
+    type Init = Settings;
+    type Input = Message;
+    type Output = Message;
+    type Root = gtk::Box;
+    type Widgets = PinEnrollmentWidgets;
+
+    fn init_root() -> Self::Root {
+        gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["pin-auth-container", "pin-enrollment-container"])
+            .focusable(true)
+            .build()
+    }
+
+    fn init(
+        init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let modules = init.modules.clone();
+
+        let password_keys: FactoryVecDeque<PasswordKey> =
+            FactoryVecDeque::builder(keypad::password_keys_container())
+                .launch()
+                .forward(
+                    sender.input_sender(),
+                    clone!(@strong modules => move|msg| match msg {
+                        PasswordKeyMessage::WidgetClicked(key) => {
+                            KeypadKey::from_title(key, &modules).into()
+                        }
+                    }),
+                );
+
+        let password_texts = FactoryVecDeque::builder(keypad::password_texts_container())
+            .launch()
+            .detach();
+
+        let step_label = gtk::Label::builder()
+            .css_classes(["pin-enrollment-step-label"])
+            .build();
+
+        let error_label = gtk::Label::builder()
+            .css_classes(["password-invalid-label"])
+            .build();
+
+        root.append(&step_label);
+        root.append(password_texts.widget());
+        root.append(&error_label);
+        root.append(password_keys.widget());
+
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(clone!(@strong sender, @strong modules => move |_, keyval, _, _| {
+            match KeypadKey::from_keyval(keyval, &modules) {
+                Some(key) => {
+                    sender.input(key.into());
+                    glib::Propagation::Stop
+                }
+                None => glib::Propagation::Proceed,
+            }
+        }));
+        root.add_controller(key_controller);
+
+        let mut model = PinEnrollment {
+            settings: init,
+            step: EnrollmentStep::New,
+            password: "".to_string(),
+            new_password: None,
+            stored_pin: None,
+            error: None,
+            failed_attempts: 0,
+            password_texts,
+            password_keys,
+            root: root.clone(),
+        };
+        keypad::populate_password_keys(
+            &mut model.password_keys,
+            &model.settings.modules,
+            &model.settings.layout.grid,
+            false,
+        );
+        model.refresh_password_texts();
+
+        let widgets = PinEnrollmentWidgets {
+            step_label,
+            error_label,
+        };
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        // key presses would spell out the pin
+        if !matches!(message, Message::PasswordKeyPressed(_)) {
+            info!("Update message is {:?}", message);
+        }
+        let password_configs = self.settings.modules.password_configs.clone();
+        match message {
+            Message::Start => {
+                let credentials =
+                    credentials::read_credentials_yml(&self.settings.credentials_path)
+                        .unwrap_or_default();
+                self.stored_pin = credentials.pin;
+                // the count carries over, starting again gives no fresh guesses
+                self.failed_attempts = credentials.failed_pin_changes;
+                self.step = match self.stored_pin {
+                    Some(_) => EnrollmentStep::Current,
+                    None => EnrollmentStep::New,
+                };
+                self.new_password = None;
+                self.error = match self.step == EnrollmentStep::Current && self.is_locked_out() {
+                    true => Some(EnrollmentError::LockedOut),
+                    false => None,
+                };
+                self.clear_password();
+            }
+            Message::PasswordKeyPressed(password_key) => {
+                if self.password.len() >= password_configs.password_length {
+                    return;
+                }
+                self.key_feedback();
+                self.error = None;
+                self.password.push_str(&password_key);
+                self.refresh_password_texts();
+
+                // fixed length pins are submitted as soon as the length is reached
+                let is_password_length_reached =
+                    self.password.len() == password_configs.password_length;
+                if !password_configs.variable_length && is_password_length_reached {
+                    self.submit(&sender);
+                }
+            }
+            Message::BackSpacePressed => {
+                if self.password.is_empty() {
+                    return;
+                }
+                self.key_feedback();
+                self.password.pop();
+                self.refresh_password_texts();
+            }
+            Message::SubmitPressed => {
+                if self.password.len() < password_configs.min_length {
+                    return;
+                }
+                self.key_feedback();
+                self.submit(&sender);
+            }
+            Message::CancelPressed => {
+                self.new_password = None;
+                self.clear_password();
+                sender.output(Message::Cancelled);
+            }
+            Message::Focus => {
+                self.root.grab_focus();
+            }
+            Message::Cancelled | Message::Completed => (),
+        }
+    }
+
+    fn update_view(&self, widgets: &mut Self::Widgets, _sender: ComponentSender<Self>) {
+        widgets.step_label.set_label(match self.step {
+            EnrollmentStep::Current => "Enter your current pin",
+            EnrollmentStep::New => "Enter a new pin",
+            EnrollmentStep::Confirm => "Confirm the new pin",
+        });
+        widgets.error_label.set_label(match self.error {
+            Some(EnrollmentError::WrongPin) => "Invalid pin, Please try again!",
+            Some(EnrollmentError::Mismatch) => "Pins do not match, Please try again!",
+            Some(EnrollmentError::SaveFailed) => "Unable to save the pin",
+            Some(EnrollmentError::LockedOut) => "Too many wrong pins, unlock the device first",
+            None => "",
+        });
+    }
+}
+
+impl PinEnrollment {
+    fn submit(&mut self, sender: &ComponentSender<Self>) {
+        let password = std::mem::take(&mut self.password);
+        self.refresh_password_texts();
+
+        match self.step {
+            EnrollmentStep::Current => {
+                if self.is_locked_out() {
+                    self.error = Some(EnrollmentError::LockedOut);
+                    return;
+                }
+
+                let is_password_wrong = match self.stored_pin.as_ref() {
+                    Some(stored_pin) => !credentials::verify_secret(&password, stored_pin),
+                    None => false,
+                };
+                if !is_password_wrong {
+                    self.record_failed_attempts(0);
+                    self.step = EnrollmentStep::New;
+                    return;
+                }
+
+                self.record_failed_attempts(self.failed_attempts + 1);
+                self.error = Some(EnrollmentError::WrongPin);
+                animations::shake(self.password_texts.widget());
+                if self.is_locked_out() {
+                    info!(task = "change pin", "pin attempts exceeded, change cancelled");
+                    self.error = None;
+                    sender.output(Message::Cancelled);
+                }
+            }
+            EnrollmentStep::New => {
+                self.new_password = Some(password);
+                self.step = EnrollmentStep::Confirm;
+            }
+            EnrollmentStep::Confirm => {
+                if self.new_password.as_ref() != Some(&password) {
+                    self.error = Some(EnrollmentError::Mismatch);
+                    self.new_password = None;
+                    self.step = EnrollmentStep::New;
+                    animations::shake(self.password_texts.widget());
+                    return;
+                }
+
+                if credentials::save_pin(&self.settings.credentials_path, &password).is_err() {
+                    self.error = Some(EnrollmentError::SaveFailed);
+                    return;
+                }
+                info!(task = "change pin", "pin changed");
+                self.new_password = None;
+                sender.output(Message::Completed);
+            }
+        }
+    }
+
+    fn is_locked_out(&self) -> bool {
+        let max_attempts = self.settings.max_attempts;
+        max_attempts > 0 && self.failed_attempts >= max_attempts
+    }
+
+    /// Keeps the count in the credentials file, a new `--change-pin`
+    /// run picks it up where the last one stopped. Unlocking resets it
+    fn record_failed_attempts(&mut self, failed: u32) {
+        self.failed_attempts = failed;
+        let _ = credentials::save_failed_pin_changes(&self.settings.credentials_path, failed);
+    }
+
+    fn clear_password(&mut self) {
+        self.password = String::from("");
+        self.refresh_password_texts();
+    }
+
+    fn refresh_password_texts(&mut self) {
+        keypad::refresh_password_texts(
+            &mut self.password_texts,
+            &self.settings.modules.password_configs,
+            self.password.len(),
+        );
+    }
+
+    fn key_feedback(&self) {
+        let haptics_settings = &self.settings.modules.haptics;
+        if haptics_settings.enabled {
+            haptics::vibrate(&haptics_settings.device_path, haptics_settings.duration);
+        }
+    }
+}
+
@@ -0,0 +1,97 @@
+use crate::errors::{LockScreenError, LockScreenErrorCodes};
+use crate::settings::AuthMethod;
+use anyhow::bail;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, fs::File, path::PathBuf};
+use tracing::info;
+
+/// # Auth Preferences
+///
+/// Struct representing the preferences.yml state file, written by the
+/// lock screen to remember the last user and the method each user picked
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct AuthPreferences {
+    pub last_user: Option<String>,       // Last user that logged in
+    pub last_method: Option<AuthMethod>, // Method picked when no user is known
+    pub users: HashMap<String, AuthMethod>,
+}
+
+impl AuthPreferences {
+    /// Method last picked by the last user, if any
+    pub fn last_user_method(&self) -> Option<AuthMethod> {
+        match self.last_user.as_ref() {
+            Some(user) => self.users.get(user).copied().or(self.last_method),
+            None => self.last_method,
+        }
+    }
+
+    /// Records the method picked, against the last user when one is known
+    pub fn set_method(&mut self, method: AuthMethod) {
+        self.last_method = Some(method);
+        if let Some(user) = self.last_user.clone() {
+            self.users.insert(user, method);
+        }
+    }
+}
+
+/// # Reads Preferences YML
+///
+/// Reads the preferences file and parses to AuthPreferences
+pub fn read_preferences_yml(path: &str) -> Result<AuthPreferences> {
+    let file_path = PathBuf::from(path);
+
+    info!(
+        task = "read_preferences",
+        "preferences file location - {:?}", file_path
+    );
+
+    // open file
+    let preferences_file_handle = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::PreferencesReadError,
+                format!("cannot read the preferences in the path - {}", e),
+            ));
+        }
+    };
+
+    // read and parse
+    let preferences: AuthPreferences = match serde_yaml::from_reader(preferences_file_handle) {
+        Ok(preferences) => preferences,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::PreferencesParseError,
+                format!("error parsing the preferences - {}", e),
+            ));
+        }
+    };
+
+    Ok(preferences)
+}
+
+/// # Writes Preferences YML
+///
+/// Serializes AuthPreferences back to the preferences file
+pub fn write_preferences_yml(path: &str, preferences: &AuthPreferences) -> Result<()> {
+    let content = match serde_yaml::to_string(preferences) {
+        Ok(content) => content,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::PreferencesWriteError,
+                format!("error serializing the preferences - {}", e),
+            ));
+        }
+    };
+
+    match fs::write(path, content) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::PreferencesWriteError,
+                format!("cannot write the preferences in the path - {}", e),
+            ));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::errors::{LockScreenError, LockScreenErrorCodes};
+use crate::settings::SessionsSettings;
+use anyhow::bail;
+use anyhow::Result;
+use std::{fs, path::Path};
+use tracing::info;
+
+/// # Session
+///
+/// A wayland session read from a `.desktop` entry, started by
+/// greetd once the user is authenticated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub name: String,
+    pub exec: String,
+}
+
+impl Session {
+    /// Exec line split into arguments, without the desktop entry
+    /// field codes (`%f`, `%U`, ...) which have no meaning here
+    pub fn command(&self) -> Vec<String> {
+        self.exec
+            .split_whitespace()
+            .filter(|arg| !(arg.len() == 2 && arg.starts_with('%')))
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+}
+
+/// # Reads Sessions
+///
+/// Lists the sessions from the `.desktop` files in the sessions directory,
+/// sorted by name, skipping hidden entries and entries without `Exec`
+pub fn read_sessions(settings: &SessionsSettings) -> Result<Vec<Session>> {
+    let entries = match fs::read_dir(&settings.sessions_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::SessionsReadError,
+                format!("cannot read {} - {}", settings.sessions_path, e),
+            ));
+        }
+    };
+
+    let mut sessions: Vec<Session> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "desktop"))
+        .filter_map(|path| parse_desktop_file(&path))
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    info!(task = "read_sessions", "found {} sessions", sessions.len());
+
+    Ok(sessions)
+}
+
+fn parse_desktop_file(path: &Path) -> Option<Session> {
+    let content = fs::read_to_string(path).ok()?;
+
+    let mut name: Option<String> = None;
+    let mut exec: Option<String> = None;
+    let mut is_desktop_entry = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            is_desktop_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !is_desktop_entry {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        match key {
+            "Name" => name = Some(value.to_string()),
+            "Exec" => exec = Some(value.to_string()),
+            "Hidden" | "NoDisplay" if value == "true" => return None,
+            _ => (),
+        }
+    }
+
+    let exec = exec.filter(|exec| !exec.is_empty())?;
+    let name = name.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or(exec.clone())
+    });
+
+    Some(Session { name, exec })
+}
+
+/// # Session Command
+///
+/// Command and environment sent to greetd with `StartSession`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionCommand {
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+}
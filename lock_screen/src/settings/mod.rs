@@ -1,10 +1,16 @@
 use crate::errors::{LockScreenError, LockScreenErrorCodes};
 use anyhow::bail;
 use anyhow::Result;
+use relm4::gtk;
 use serde::{Deserialize, Serialize};
+use chrono::format::{Item, StrftimeItems};
 use std::{env, fs::File, path::PathBuf};
 use tracing::{debug, info};
 
+/// Clock formats used when none or an invalid one is configured
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+pub const DEFAULT_DATE_FORMAT: &str = "%A, %d %B";
+
 /// # LockScreen Settings
 ///
 /// Struct representing the settings.yml configuration file,
@@ -17,6 +23,12 @@ pub struct LockScreenSettings {
     pub title: String,          // Sets the window title
     pub layout: LayoutSettings,
     pub modules: Modules,
+    pub auth: AuthSettings,
+    pub users: UsersSettings,
+    pub sessions: SessionsSettings,
+    pub authenticators: AuthenticatorsSettings,
+    pub dbus: DbusSettings,
+    pub transitions: TransitionsSettings,
     pub css: CssConfigs
 }
 
@@ -28,6 +40,12 @@ impl Default for LockScreenSettings {
             title: String::from("Lock Screen"),
             layout: LayoutSettings::default(),
             modules: Modules::default(),
+            auth: AuthSettings::default(),
+            users: UsersSettings::default(),
+            sessions: SessionsSettings::default(),
+            authenticators: AuthenticatorsSettings::default(),
+            dbus: DbusSettings::default(),
+            transitions: TransitionsSettings::default(),
             css: CssConfigs::default()
         }
     }
@@ -83,6 +101,223 @@ pub struct LayoutSettings {
     pub grid: Vec<String>, //Items that will in grid
 }
 
+/// # Auth Settings
+///
+/// Part of the settings.yml to control which authentication
+/// methods are offered and which one is shown first
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AuthSettings {
+    pub default_method: AuthMethod, // Method used when nothing is remembered
+    pub pin_enabled: bool,
+    pub password_enabled: bool,
+    pub pattern_enabled: bool,
+    pub remember_last_method: bool, // Prefer the method last picked by the last user
+    pub max_pin_attempts: u32,      // Wrong PINs in a row before PIN and pattern are locked out, 0 disables
+    pub max_pattern_attempts: u32,  // Wrong patterns in a row before PIN and pattern are locked out, 0 disables
+    pub credentials_path: String,   // File holding the hashed PIN and pattern
+    pub show_home_page: bool,       // Start on the home page instead of the auth page
+    pub preferences_path: String,   // File used to remember the last user and method
+    pub login_timeout: u64,         // Seconds to wait on the login manager before giving up, 0 waits forever
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            default_method: AuthMethod::Pin,
+            pin_enabled: true,
+            password_enabled: true,
+            pattern_enabled: false,
+            remember_last_method: true,
+            max_pin_attempts: 5,
+            max_pattern_attempts: 5,
+            credentials_path: "credentials.yml".to_string(),
+            show_home_page: true,
+            preferences_path: "preferences.yml".to_string(),
+            login_timeout: 10,
+        }
+    }
+}
+
+impl AuthSettings {
+    pub fn is_enabled(&self, method: AuthMethod) -> bool {
+        match method {
+            AuthMethod::Pin => self.pin_enabled,
+            AuthMethod::Password => self.password_enabled,
+            AuthMethod::Pattern => self.pattern_enabled,
+        }
+    }
+}
+
+/// # Users Settings
+///
+/// Part of the settings.yml to control which users are
+/// listed in the user picker of the password screen
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct UsersSettings {
+    pub min_uid: u32,                   // Lowest UID considered a login user
+    pub max_uid: u32,                   // Highest UID considered a login user
+    pub hidden_users: Vec<String>,      // Users never shown in the picker
+    pub default_avatar: Option<String>, // Used when the user has no ~/.face
+    pub allow_manual_username: bool,    // Keep the username input next to the picker
+}
+
+impl Default for UsersSettings {
+    fn default() -> Self {
+        Self {
+            min_uid: 1000,
+            max_uid: 60000,
+            hidden_users: vec![],
+            default_avatar: None,
+            allow_manual_username: false,
+        }
+    }
+}
+
+/// # Sessions Settings
+///
+/// Part of the settings.yml to run the lock screen as a greeter,
+/// starting the chosen session through greetd after login
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct SessionsSettings {
+    pub enabled: bool,                   // Start a session after login and exit
+    pub sessions_path: String,           // Directory with the session .desktop files
+    pub default_session: Option<String>, // Session name selected when nothing is remembered
+    pub env: Vec<String>,                // KEY=VALUE pairs passed to the session
+}
+
+impl Default for SessionsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sessions_path: "/usr/share/wayland-sessions".to_string(),
+            default_session: None,
+            env: vec![],
+        }
+    }
+}
+
+/// # Authenticators Settings
+///
+/// Part of the settings.yml registering the authentication methods
+/// that run in the background next to the auth pages
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct AuthenticatorsSettings {
+    pub fingerprint: FingerprintAuthenticatorSettings,
+}
+
+/// Fingerprint verification through fprintd
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct FingerprintAuthenticatorSettings {
+    pub enabled: bool,
+    pub finger: String,    // Enrolled finger to verify, "any" for all of them
+    pub max_attempts: u32, // Unrecognized fingers before giving up, 0 disables
+}
+
+impl Default for FingerprintAuthenticatorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            finger: "any".to_string(),
+            max_attempts: 5,
+        }
+    }
+}
+
+/// # Dbus Settings
+///
+/// Part of the settings.yml to control the `org.mecha.LockScreen`
+/// service other processes use to talk to the lock screen
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct DbusSettings {
+    pub enabled: bool,
+    pub system_bus: bool, // Claim the name on the system bus instead of the session bus
+}
+
+impl Default for DbusSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            system_bus: false,
+        }
+    }
+}
+
+/// # Transitions Settings
+///
+/// Part of the settings.yml to control how the lock screen
+/// moves between pages and how it goes away on unlock
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TransitionsSettings {
+    pub duration: u32,                // Milliseconds a page transition takes
+    pub home_to_auth: TransitionType, // From the home page to an auth page
+    pub auth_to_home: TransitionType, // From an auth page back to the home page
+    pub between_auth: TransitionType, // From one auth page to another
+    pub swipe_navigation: bool,       // Swipe up from home to auth, down to go back
+    pub unlock_fade_duration: u32,    // Milliseconds to fade out on unlock, 0 disables
+}
+
+impl Default for TransitionsSettings {
+    fn default() -> Self {
+        Self {
+            duration: 250,
+            home_to_auth: TransitionType::SlideUp,
+            auth_to_home: TransitionType::SlideDown,
+            between_auth: TransitionType::Crossfade,
+            swipe_navigation: true,
+            unlock_fade_duration: 300,
+        }
+    }
+}
+
+/// Stack transitions that can be picked in settings.yml
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+pub enum TransitionType {
+    #[default]
+    None,
+    Crossfade,
+    SlideUp,
+    SlideDown,
+    SlideLeft,
+    SlideRight,
+    OverUp,
+    OverDown,
+    OverLeft,
+    OverRight,
+    UnderUp,
+    UnderDown,
+    UnderLeft,
+    UnderRight,
+}
+
+impl From<TransitionType> for gtk::StackTransitionType {
+    fn from(transition: TransitionType) -> Self {
+        match transition {
+            TransitionType::None => gtk::StackTransitionType::None,
+            TransitionType::Crossfade => gtk::StackTransitionType::Crossfade,
+            TransitionType::SlideUp => gtk::StackTransitionType::SlideUp,
+            TransitionType::SlideDown => gtk::StackTransitionType::SlideDown,
+            TransitionType::SlideLeft => gtk::StackTransitionType::SlideLeft,
+            TransitionType::SlideRight => gtk::StackTransitionType::SlideRight,
+            TransitionType::OverUp => gtk::StackTransitionType::OverUp,
+            TransitionType::OverDown => gtk::StackTransitionType::OverDown,
+            TransitionType::OverLeft => gtk::StackTransitionType::OverLeft,
+            TransitionType::OverRight => gtk::StackTransitionType::OverRight,
+            TransitionType::UnderUp => gtk::StackTransitionType::UnderUp,
+            TransitionType::UnderDown => gtk::StackTransitionType::UnderDown,
+            TransitionType::UnderLeft => gtk::StackTransitionType::UnderLeft,
+            TransitionType::UnderRight => gtk::StackTransitionType::UnderRight,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+pub enum AuthMethod {
+    #[default]
+    Pin,
+    Password,
+    Pattern,
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct CssConfigs {
     pub default: String,
@@ -137,6 +372,7 @@ pub struct BackModule {
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct SubmitModule {
     pub icon: DefaultIconPaths,
+    pub title: String,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -144,10 +380,33 @@ pub struct HomePasswordModule {
     pub icon: DefaultIconPaths,
 }
 
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct HomePatternModule {
+    pub icon: DefaultIconPaths,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct PatternConfigsModule {
+    pub min_length: usize, // Fewest dots a pattern has to connect
+    pub show_path: bool,   // Draw the lines while the pattern is entered
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct PasswordConfigsModule {
     pub keys_allowed: Vec<String>,
-    pub password_length: usize,
+    pub password_length: usize, // Pin length, or the max length when variable_length is set
+    pub min_length: usize,      // Shortest pin accepted by the submit key
+    pub variable_length: bool,  // Submit with the submit key instead of on reaching the length
+    pub scramble_keypad: bool,  // Shuffle the pin keys on every attempt
+}
+
+/// Vibration on key press, through a sysfs vibrator
+/// exposed as a led with the `transient` trigger
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct HapticsModule {
+    pub enabled: bool,
+    pub device_path: String,
+    pub duration: u32, // Vibration duration in ms
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -160,6 +419,96 @@ pub struct UnPeekPasswordModule {
     pub icon: DefaultIconPaths,
 }
 
+/// Clock module shown on the home page
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct ClockModule {
+    pub time_format: String,
+    pub date_format: String,
+}
+
+/// Wifi module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct WifiModule {
+    pub icon: WifiIconPaths,
+}
+
+/// Battery module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct BatteryModule {
+    pub icon: BatteryIconPaths,
+}
+
+/// Messages shown on the password screen for each login outcome,
+/// kept in the settings so they can be translated
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AuthMessagesModule {
+    pub success: String,
+    pub bad_credentials: String,
+    pub account_locked: String,
+    pub pam_error: String,
+    pub daemon_unreachable: String,
+    pub timeout: String,
+    pub no_session: String,
+}
+
+/// Owner details shown on the home page
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct OwnerMessageModule {
+    pub enabled: bool,
+    pub title: String,
+    pub message: String,
+}
+
+/// Flashlight toggle on the home page, through a
+/// sysfs led driving the torch
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct FlashlightModule {
+    pub enabled: bool,
+    pub device_path: String,
+    pub icon: DefaultIconPaths,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct EmergencyContact {
+    pub name: String,
+    pub number: String,
+}
+
+/// Emergency contacts listed on the home page
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct EmergencyModule {
+    pub enabled: bool,
+    pub icon: DefaultIconPaths,
+    pub contacts: Vec<EmergencyContact>,
+}
+
+/// Icon paths for wifi module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct WifiIconPaths {
+    pub off: Option<String>,
+    pub on: Option<String>,
+    pub low: Option<String>,
+    pub weak: Option<String>,
+    pub good: Option<String>,
+    pub strong: Option<String>,
+}
+
+/// Icon paths for battery module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct BatteryIconPaths {
+    pub level_100: Option<String>,
+    pub level_90: Option<String>,
+    pub level_80: Option<String>,
+    pub level_70: Option<String>,
+    pub level_60: Option<String>,
+    pub level_50: Option<String>,
+    pub level_40: Option<String>,
+    pub level_30: Option<String>,
+    pub level_20: Option<String>,
+    pub level_10: Option<String>,
+    pub level_0: Option<String>,
+}
+
 /// # Modules
 ///
 /// Options that will be visible in lock screen
@@ -172,9 +521,19 @@ pub struct Modules {
     pub back: BackModule,
     pub submit: SubmitModule,
     pub home_password: HomePasswordModule,
+    pub home_pattern: HomePatternModule,
     pub password_configs: PasswordConfigsModule,
+    pub pattern_configs: PatternConfigsModule,
     pub peek_password: PeekPasswordModule,
     pub un_peek_password: UnPeekPasswordModule,
+    pub clock: ClockModule,
+    pub wifi: WifiModule,
+    pub battery: BatteryModule,
+    pub haptics: HapticsModule,
+    pub owner_message: OwnerMessageModule,
+    pub flashlight: FlashlightModule,
+    pub emergency: EmergencyModule,
+    pub auth_messages: AuthMessagesModule,
 }
 
 impl Default for WindowSettings {
@@ -214,22 +573,91 @@ impl Default for Modules {
             password_configs: PasswordConfigsModule {
                 keys_allowed: vec![],
                 password_length: 0,
+                min_length: 0,
+                variable_length: false,
+                scramble_keypad: false,
             },
             back: BackModule {
                 icon: DefaultIconPaths { default: None },
             },
             submit: SubmitModule {
                 icon: DefaultIconPaths { default: None },
+                title: "".to_string(),
             },
             home_password: HomePasswordModule {
                 icon: DefaultIconPaths { default: None },
             },
+            home_pattern: HomePatternModule {
+                icon: DefaultIconPaths { default: None },
+            },
+            pattern_configs: PatternConfigsModule {
+                min_length: 4,
+                show_path: true,
+            },
             peek_password: PeekPasswordModule {
                 icon: DefaultIconPaths { default: None },
             },
             un_peek_password: UnPeekPasswordModule {
                 icon: DefaultIconPaths { default: None },
             },
+            clock: ClockModule {
+                time_format: DEFAULT_TIME_FORMAT.to_string(),
+                date_format: DEFAULT_DATE_FORMAT.to_string(),
+            },
+            wifi: WifiModule {
+                icon: WifiIconPaths {
+                    off: None,
+                    on: None,
+                    low: None,
+                    weak: None,
+                    good: None,
+                    strong: None,
+                },
+            },
+            battery: BatteryModule {
+                icon: BatteryIconPaths {
+                    level_100: None,
+                    level_90: None,
+                    level_80: None,
+                    level_70: None,
+                    level_60: None,
+                    level_50: None,
+                    level_40: None,
+                    level_30: None,
+                    level_20: None,
+                    level_10: None,
+                    level_0: None,
+                },
+            },
+            haptics: HapticsModule {
+                enabled: false,
+                device_path: "".to_string(),
+                duration: 0,
+            },
+            owner_message: OwnerMessageModule {
+                enabled: false,
+                title: "".to_string(),
+                message: "".to_string(),
+            },
+            flashlight: FlashlightModule {
+                enabled: false,
+                device_path: "".to_string(),
+                icon: DefaultIconPaths { default: None },
+            },
+            emergency: EmergencyModule {
+                enabled: false,
+                icon: DefaultIconPaths { default: None },
+                contacts: vec![],
+            },
+            auth_messages: AuthMessagesModule {
+                success: "Login successful".to_string(),
+                bad_credentials: "Incorrect username or password".to_string(),
+                account_locked: "Account is locked, try again later".to_string(),
+                pam_error: "Unable to log in, please try again".to_string(),
+                daemon_unreachable: "Login service is not available".to_string(),
+                timeout: "Login service did not respond in time".to_string(),
+                no_session: "No session is available to start".to_string(),
+            },
         }
     }
 }
@@ -279,7 +707,7 @@ pub fn read_settings_yml() -> Result<LockScreenSettings> {
     };
 
     // read and parse
-    let config: LockScreenSettings = match serde_yaml::from_reader(settings_file_handle) {
+    let mut config: LockScreenSettings = match serde_yaml::from_reader(settings_file_handle) {
         Ok(config) => config,
         Err(e) => {
             bail!(LockScreenError::new(
@@ -289,5 +717,42 @@ pub fn read_settings_yml() -> Result<LockScreenSettings> {
         }
     };
 
+    // formatting with an invalid strftime string panics, so the clock falls back instead
+    let clock = &mut config.modules.clock;
+    for (format, default_format) in [
+        (&mut clock.time_format, DEFAULT_TIME_FORMAT),
+        (&mut clock.date_format, DEFAULT_DATE_FORMAT),
+    ] {
+        if !is_valid_clock_format(format) {
+            LockScreenError::new(
+                LockScreenErrorCodes::InvalidClockFormat,
+                format!(
+                    "clock format {:?} is not a valid strftime format, using {:?}",
+                    format, default_format
+                ),
+            );
+            *format = default_format.to_string();
+        }
+    }
+
     Ok(config)
 }
+
+/// Whether chrono can format a time with the strftime string
+pub fn is_valid_clock_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_formats_are_validated() {
+        assert!(is_valid_clock_format(DEFAULT_TIME_FORMAT));
+        assert!(is_valid_clock_format(DEFAULT_DATE_FORMAT));
+        assert!(is_valid_clock_format("%I:%M %p, %-d.%m.%Y"));
+        assert!(!is_valid_clock_format("%H:%"));
+        assert!(!is_valid_clock_format("%Q"));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::errors::{LockScreenError, LockScreenErrorCodes};
+use crate::settings::UsersSettings;
+use anyhow::bail;
+use anyhow::Result;
+use std::{fs, path::Path};
+use tracing::info;
+
+const PASSWD_PATH: &str = "/etc/passwd";
+const ACCOUNTS_SERVICE_ICONS_PATH: &str = "/var/lib/AccountsService/icons";
+
+/// # User
+///
+/// A login-capable user shown in the user picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub username: String,
+    pub display_name: String,
+    pub avatar: Option<String>,
+}
+
+/// # Reads Login Users
+///
+/// Enumerates users from `/etc/passwd` within the configured UID range,
+/// skipping hidden users and accounts without a login shell
+pub fn read_login_users(settings: &UsersSettings) -> Result<Vec<User>> {
+    let content = match fs::read_to_string(PASSWD_PATH) {
+        Ok(content) => content,
+        Err(e) => {
+            bail!(LockScreenError::new(
+                LockScreenErrorCodes::UsersReadError,
+                format!("cannot read {} - {}", PASSWD_PATH, e),
+            ));
+        }
+    };
+
+    let users: Vec<User> = content
+        .lines()
+        .filter_map(|line| parse_passwd_line(line, settings))
+        .collect();
+
+    info!(task = "read_users", "found {} login users", users.len());
+
+    Ok(users)
+}
+
+fn parse_passwd_line(line: &str, settings: &UsersSettings) -> Option<User> {
+    let fields: Vec<&str> = line.split(':').collect();
+    if fields.len() < 7 {
+        return None;
+    }
+
+    let username = fields[0];
+    let uid = fields[2].parse::<u32>().ok()?;
+    let gecos = fields[4];
+    let home = fields[5];
+    let shell = fields[6];
+
+    if uid < settings.min_uid || uid > settings.max_uid {
+        return None;
+    }
+    if shell.ends_with("nologin") || shell.ends_with("false") {
+        return None;
+    }
+    if settings.hidden_users.iter().any(|hidden| hidden == username) {
+        return None;
+    }
+
+    // gecos is "Full Name,Room,Phone,..." - only the name is of interest
+    let display_name = match gecos.split(',').next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => username.to_string(),
+    };
+
+    Some(User {
+        username: username.to_string(),
+        display_name,
+        avatar: find_avatar(username, home).or(settings.default_avatar.clone()),
+    })
+}
+
+/// Looks for the user's `~/.face`, then the icon set through AccountsService
+fn find_avatar(username: &str, home: &str) -> Option<String> {
+    let candidates = [
+        Path::new(home).join(".face"),
+        Path::new(ACCOUNTS_SERVICE_ICONS_PATH).join(username),
+    ];
+
+    candidates
+        .iter()
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+}
@@ -1,2 +1,3 @@
 pub mod password_key;
 pub mod password_text;
+pub mod user_tile;
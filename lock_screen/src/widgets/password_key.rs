@@ -37,7 +37,8 @@ pub(crate) struct PasswordKey {
 
 #[derive(Debug)]
 pub struct PasswordKeyWidgets {
-    password_key_label: gtk::Label
+    password_key_label: gtk::Label,
+    action_button: gtk::Box,
 }
 
 // #[relm4::factory(pub(crate))]
@@ -135,7 +136,8 @@ impl FactoryComponent for PasswordKey {
         // }));
 
         let widgets = PasswordKeyWidgets {
-            password_key_label: label
+            password_key_label: label,
+            action_button,
         };
 
         widgets
@@ -143,6 +145,7 @@ impl FactoryComponent for PasswordKey {
 
     fn update_view(&self, widgets: &mut Self::Widgets, sender: FactorySender<Self>) {
         widgets.password_key_label.set_class_active("password-key-focus", self.is_pressing);
+        widgets.action_button.set_class_active("password-key-pressed", self.is_pressing);
     }
     
 }
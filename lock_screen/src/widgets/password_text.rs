@@ -10,6 +10,7 @@ use tracing::info;
 #[derive(Clone, Debug)]
 pub enum Message {
     ToggleFilled,
+    SetFilled(bool),
 }
 
 /// Configuration for the password text widget
@@ -52,6 +53,9 @@ impl FactoryComponent for PasswordText {
             Message::ToggleFilled => {
                 self.settings.is_filled = !self.settings.is_filled;
             }
+            Message::SetFilled(is_filled) => {
+                self.settings.is_filled = is_filled;
+            }
         }
     }
 
@@ -0,0 +1,108 @@
+use gtk::prelude::*;
+
+use relm4::factory::{DynamicIndex, FactoryComponent, FactorySender};
+use relm4::gtk::glib::clone;
+use relm4::{gtk, gtk::GestureClick, RelmWidgetExt};
+
+use custom_utils::get_image_from_path;
+use tracing::info;
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    UserClicked(String),
+}
+
+#[derive(Clone, Debug)]
+pub enum InputMessage {
+    SetSelected(bool),
+}
+
+/// Configuration for the user tile widget
+#[derive(PartialEq, Eq, Default, Debug, Clone)]
+pub struct UserTileSettings {
+    pub username: String,
+    pub display_name: String,
+    pub avatar: Option<String>,
+    pub is_selected: bool,
+}
+
+/// User tile component, one per user in the user picker.
+#[derive(PartialEq, Eq, Default, Debug, Clone)]
+pub(crate) struct UserTile {
+    pub settings: UserTileSettings,
+}
+
+pub struct UserTileWidgets {
+    user_tile_box: gtk::Box,
+}
+
+impl FactoryComponent for UserTile {
+    type Init = UserTileSettings;
+    type Input = InputMessage;
+    type Output = Message;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+    type Widgets = UserTileWidgets;
+    type Root = gtk::Box;
+    type Index = DynamicIndex;
+
+    fn init_root(&self) -> Self::Root {
+        gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .halign(gtk::Align::Center)
+            .build()
+    }
+
+    fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+        Self { settings: value }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: FactorySender<Self>) {
+        info!("user tile update message {:?}", msg);
+        match msg {
+            InputMessage::SetSelected(is_selected) => {
+                self.settings.is_selected = is_selected;
+            }
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: &Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let user_tile_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .css_classes(["user-tile"])
+            .spacing(6)
+            .build();
+
+        let avatar_image = get_image_from_path(self.settings.avatar.clone(), &["user-tile-avatar"]);
+
+        let name_label = gtk::Label::builder()
+            .label(&self.settings.display_name)
+            .css_classes(["user-tile-label"])
+            .build();
+
+        user_tile_box.append(&avatar_image);
+        user_tile_box.append(&name_label);
+        root.append(&user_tile_box);
+
+        let username = self.settings.username.to_owned();
+        let left_click_gesture = GestureClick::builder().button(0).build();
+        left_click_gesture.connect_released(clone!(@strong sender => move |_, _, _, _| {
+            sender.output(Message::UserClicked(username.to_owned()));
+        }));
+        root.add_controller(left_click_gesture);
+
+        UserTileWidgets { user_tile_box }
+    }
+
+    fn update_view(&self, widgets: &mut Self::Widgets, _sender: FactorySender<Self>) {
+        widgets
+            .user_tile_box
+            .set_class_active("user-tile-selected", self.settings.is_selected);
+    }
+}
@@ -1,3 +1 @@
-pub mod battery_client;
 pub mod bluetooth_client;
-pub mod network_client;
@@ -6,7 +6,8 @@ use custom_utils::get_image_from_path;
 use dbus::message::MatchRule;
 use dbus::nonblock;
 use dbus_tokio::connection;
-use grpc::battery_client::BatteryManagerClient;
+use grpc_clients::battery_client::BatteryManagerClient;
+use grpc_clients::network_client::NetworkManagerClient;
 use gtk::{
     gdk, gio, glib,
     prelude::{BoxExt, GtkWindowExt, WidgetExt},
@@ -30,7 +31,6 @@ pub mod errors;
 mod grpc;
 use crate::errors::{StatusBarError, StatusBarErrorCodes};
 use crate::grpc::bluetooth_client::BluetoothManagerClient;
-use crate::grpc::network_client::NetworkManagerClient;
 use crate::settings::StatusBarSettings;
 use crate::theme::StatusBarTheme;
 // #[allow(non_snake_case)]